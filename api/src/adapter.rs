@@ -1,46 +1,75 @@
+use ckb_jsonrpc_types::Either;
+use ckb_types::{core::TransactionView, packed, prelude::Entity};
 use common::traits::{
     api::APIAdapter,
     async_trait,
+    ckb_rpc_client::CkbRpc,
     query::TransactionStorage,
     smt::{DelegateSmtStorage, RewardSmtStorage, StakeSmtStorage},
 };
-use common::types::{relation_db::transaction::Model, smt::Address};
-use common::Result;
+use common::types::{
+    api::{OperationType, SortOrder},
+    relation_db::transaction::Model,
+    smt::{Address, Amount, Epoch, Root},
+    H256,
+};
+use common::utils::convert::{to_ckb_h160, to_ckb_h256};
+use common::{AnyError, Result};
+use std::collections::HashMap;
 use std::sync::Arc;
+use tx_builder::ckb::helper::{Delegate, Metadata};
 
 #[derive(Clone)]
-pub struct DefaultAPIAdapter<T, S> {
+pub struct DefaultAPIAdapter<T, S, C> {
     relation_storage: Arc<T>,
-    _smt_storage:     Arc<S>,
+    smt_storage:      Arc<S>,
+    ckb_rpc:          Arc<C>,
+    metadata_type_id: Option<ckb_types::H256>,
 }
 
-impl<T, S> DefaultAPIAdapter<T, S>
+impl<T, S, C> DefaultAPIAdapter<T, S, C>
 where
     T: TransactionStorage + 'static,
     S: StakeSmtStorage + DelegateSmtStorage + RewardSmtStorage + 'static,
+    C: CkbRpc + 'static,
 {
-    pub fn new(relation_storage: Arc<T>, smt_storage: Arc<S>) -> Self {
+    pub fn new(relation_storage: Arc<T>, smt_storage: Arc<S>, ckb_rpc: Arc<C>) -> Self {
         Self {
             relation_storage,
-            _smt_storage: smt_storage,
+            smt_storage,
+            ckb_rpc,
+            metadata_type_id: None,
         }
     }
+
+    /// Set the metadata type id of the deployment, which the delegate
+    /// requirement cells are found by.
+    pub fn with_metadata_type_id(mut self, metadata_type_id: H256) -> Self {
+        self.metadata_type_id = Some(to_ckb_h256(&metadata_type_id));
+        self
+    }
 }
 
 #[async_trait]
-impl<T, S> APIAdapter for DefaultAPIAdapter<T, S>
+impl<T, S, C> APIAdapter for DefaultAPIAdapter<T, S, C>
 where
     T: TransactionStorage + Sync + Send + 'static,
     S: StakeSmtStorage + DelegateSmtStorage + RewardSmtStorage + Sync + Send + 'static,
+    C: CkbRpc + 'static,
 {
+    async fn ping(&self) -> Result<()> {
+        self.relation_storage.ping().await
+    }
+
     async fn get_records_by_address(
         &self,
         addr: Address,
+        order: SortOrder,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>> {
         self.relation_storage
-            .get_records_by_address(addr, offset, limit)
+            .get_records_by_address(addr, order, offset, limit)
             .await
     }
 
@@ -48,11 +77,25 @@ where
         &self,
         addr: Address,
         operation: u32,
+        order: SortOrder,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>> {
         self.relation_storage
-            .get_operation_history(addr, operation, offset, limit)
+            .get_operation_history(addr, operation, order, offset, limit)
+            .await
+    }
+
+    async fn get_operation_history_after(
+        &self,
+        addr: Address,
+        operation: u32,
+        order: SortOrder,
+        after: Option<u32>,
+        limit: u64,
+    ) -> Result<Vec<Model>> {
+        self.relation_storage
+            .get_operation_history_after(addr, operation, order, after, limit)
             .await
     }
 
@@ -67,14 +110,135 @@ where
             .await
     }
 
-    async fn get_top_stake_address(&self, operation: u32) -> Result<Vec<Model>> {
-        self.relation_storage.get_top_stake_address(operation).await
+    async fn get_smt_amount_by_epoch(
+        &self,
+        operation: u32,
+        epoch: Epoch,
+    ) -> Result<Option<Amount>> {
+        let stakers = StakeSmtStorage::get_sub_leaves(self.smt_storage.as_ref(), epoch).await?;
+        if stakers.is_empty() {
+            return Ok(None);
+        }
+
+        if operation == OperationType::Stake as u32 {
+            Ok(Some(stakers.values().sum()))
+        } else if operation == OperationType::Delegate as u32 {
+            let mut total = 0;
+            for staker in stakers.into_keys() {
+                total +=
+                    DelegateSmtStorage::get_sub_leaves(self.smt_storage.as_ref(), epoch, staker)
+                        .await?
+                        .values()
+                        .sum::<Amount>();
+            }
+            Ok(Some(total))
+        } else {
+            Ok(None)
+        }
+    }
+
+    async fn get_history_by_epoch(
+        &self,
+        epoch: u32,
+        operation: Option<u32>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>> {
+        self.relation_storage
+            .get_history_by_epoch(epoch, operation, offset, limit)
+            .await
+    }
+
+    async fn get_top_stake_address(
+        &self,
+        operation: u32,
+        exclude: Vec<Address>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>> {
+        self.relation_storage
+            .get_top_stake_address(operation, exclude, offset, limit)
+            .await
     }
 
     async fn get_address_state(&self, addr: Address) -> Result<Vec<Model>> {
         self.relation_storage.get_address_state(addr).await
     }
 
+    async fn get_delegators_of_staker(&self, staker: Address) -> Result<Vec<Model>> {
+        self.relation_storage.get_delegators_of_staker(staker).await
+    }
+
+    async fn get_maximum_delegators(&self, staker: Address) -> Result<u32> {
+        let metadata_type_id = self
+            .metadata_type_id
+            .as_ref()
+            .ok_or_else(|| AnyError::msg("the metadata type id is not configured"))?;
+        Delegate::maximum_delegators(
+            self.ckb_rpc.as_ref(),
+            metadata_type_id,
+            &to_ckb_h160(&staker),
+        )
+        .await
+    }
+
+    async fn get_quorum(&self) -> Result<u16> {
+        let metadata_type_id = self
+            .metadata_type_id
+            .as_ref()
+            .ok_or_else(|| AnyError::msg("the metadata type id is not configured"))?;
+        Metadata::quorum(self.ckb_rpc.as_ref(), metadata_type_id).await
+    }
+
+    async fn get_smt_roots(&self, start: Epoch, end: Epoch) -> Result<Vec<(Epoch, Root)>> {
+        StakeSmtStorage::root_history(self.smt_storage.as_ref(), start, end).await
+    }
+
+    async fn get_stakers(&self, epoch: Epoch) -> Result<HashMap<Address, Amount>> {
+        StakeSmtStorage::get_sub_leaves(self.smt_storage.as_ref(), epoch).await
+    }
+
+    async fn is_staker(&self, epoch: Epoch, addr: Address) -> Result<bool> {
+        StakeSmtStorage::contains(self.smt_storage.as_ref(), epoch, addr).await
+    }
+
+    async fn is_delegator(
+        &self,
+        epoch: Epoch,
+        staker: Address,
+        delegator: Address,
+    ) -> Result<bool> {
+        DelegateSmtStorage::contains(self.smt_storage.as_ref(), epoch, staker, delegator).await
+    }
+
+    async fn get_delegator_count(&self, epoch: Epoch, staker: Address) -> Result<u32> {
+        let delegators =
+            DelegateSmtStorage::get_sub_leaves(self.smt_storage.as_ref(), epoch, staker).await?;
+        Ok(delegators.len() as u32)
+    }
+
+    async fn get_records_by_addresses(&self, addrs: Vec<Address>) -> Result<Vec<Model>> {
+        self.relation_storage.get_records_by_addresses(addrs).await
+    }
+
+    async fn get_record_by_tx_hash(&self, tx_hash: H256) -> Result<Option<Model>> {
+        self.relation_storage.get_record_by_tx_hash(tx_hash).await
+    }
+
+    async fn get_ckb_transaction(&self, tx_hash: H256) -> Result<Option<TransactionView>> {
+        let tx = self.ckb_rpc.get_transaction(to_ckb_h256(&tx_hash)).await?;
+        let tx = match tx.and_then(|tx| tx.transaction) {
+            Some(tx) => tx,
+            None => return Ok(None),
+        };
+
+        let tx = match tx.inner {
+            Either::Left(view) => packed::Transaction::from(view.inner),
+            Either::Right(bytes) => packed::Transaction::from_slice(bytes.as_bytes())?,
+        };
+        Ok(Some(tx.into_view()))
+    }
+
     async fn get_latest_stake_transactions(&self, offset: u64, limit: u64) -> Result<Vec<Model>> {
         self.relation_storage
             .get_latest_stake_transactions(offset, limit)
@@ -1,35 +1,64 @@
 pub mod axon;
 pub mod operation;
 pub mod query;
+mod tls;
+
+pub use tls::TlsOptions;
+
 use crate::error::ApiError;
 use crate::jsonrpc::operation::OperationRpc;
 use crate::jsonrpc::query::{AxonStatusRpc, StatusRpcModule};
 
 use common::types::api::{
-    AddressAmount, ChainState, HistoryEvent, OperationType, RewardHistory, RewardState,
-    StakeAmount, StakeHistory, StakeRate, StakeState, StakeTransaction,
+    AccountSummary, AddressAmount, AddressFormat, AmountSource, ChainState, Cursor,
+    DelegateCapacity, EpochHistory, EpochRoot, HistoryEvent, OperationType, RewardHistory,
+    RewardState, ScriptConfig, SortOrder, StakeAmount, StakeHistory, StakeRate, StakeState,
+    StakeTransaction, StakeTransactionDetail,
 };
 use common::types::smt::Address;
+use common::types::tx_builder::NetworkType;
 use common::types::Transaction;
+use common::utils::round::AmountDisplay;
 use common::{traits::api::APIAdapter, types::H256};
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::server::{ServerBuilder, ServerHandle};
-use tokio::net::ToSocketAddrs;
+use tokio::net::{lookup_host, TcpSocket, ToSocketAddrs};
+use tokio::sync::watch;
 
-use std::sync::Arc;
+use std::{
+    io,
+    net::{SocketAddr, TcpListener},
+    sync::Arc,
+};
 
 #[rpc(server)]
 pub trait AccountHistoryRpc {
     #[method(name = "getStakeRate")]
-    async fn get_stake_rate(&self, addr: Address) -> RpcResult<StakeRate>;
+    async fn get_stake_rate(
+        &self,
+        addr: Address,
+        address_format: Option<AddressFormat>,
+    ) -> RpcResult<StakeRate>;
 
     #[method(name = "getStakeState")]
     async fn get_stake_state(&self, addr: Address) -> RpcResult<StakeState>;
 
+    #[method(name = "getAccountSummary")]
+    async fn get_account_summary(
+        &self,
+        addr: Address,
+        address_format: Option<AddressFormat>,
+    ) -> RpcResult<AccountSummary>;
+
     #[method(name = "getRewardState")]
     async fn get_reward_state(&self, addr: Address) -> RpcResult<RewardState>;
 
+    /// The reward states of the addresses, in the order given. An address
+    /// without records gets a zero state. At most 100 addresses are taken.
+    #[method(name = "getRewardStates")]
+    async fn get_reward_states(&self, addrs: Vec<Address>) -> RpcResult<Vec<RewardState>>;
+
     #[method(name = "getStakeHistory")]
     async fn get_stake_history(
         &self,
@@ -38,31 +67,122 @@ pub trait AccountHistoryRpc {
         page_size: u64,
         enent: HistoryEvent,
         operation_type: OperationType,
+        after: Option<Cursor>,
+        order: Option<SortOrder>,
     ) -> RpcResult<Vec<StakeHistory>>;
 
+    /// The reward records of the address, paged like `getStakeHistory` but
+    /// oldest first by default.
     #[method(name = "getRewardHistory")]
     async fn get_reward_history(
         &self,
         addr: Address,
         page_number: u64,
         page_size: u64,
-    ) -> RpcResult<RewardHistory>;
+        after: Option<Cursor>,
+        order: Option<SortOrder>,
+    ) -> RpcResult<Vec<RewardHistory>>;
 
+    /// The amounts of the epochs, read from the SMT by default. See
+    /// [`AmountSource`] for the semantics of each source. From the history the
+    /// pages are over its rows. From the SMT the pages are over the epoch
+    /// numbers: page `n` covers the epochs `(n - 1) * page_size` to
+    /// `n * page_size - 1`, with one entry per epoch which has a stake root,
+    /// so a page can come back short or empty before the last epoch. The page
+    /// size of the SMT source is at most 1000.
     #[method(name = "getStakeAmountByEpoch")]
     async fn get_stake_amount_by_epoch(
         &self,
         operation_type: OperationType,
         page_number: u64,
         page_size: u64,
+        source: Option<AmountSource>,
+    ) -> RpcResult<Vec<StakeAmount>>;
+
+    /// The records of all addresses in the epoch, of every operation unless
+    /// one is given.
+    #[method(name = "getEpochHistory")]
+    async fn get_epoch_history(
+        &self,
+        epoch: u32,
+        operation_type: Option<OperationType>,
+        page_number: u64,
+        page_size: u64,
+    ) -> RpcResult<Vec<EpochHistory>>;
+
+    /// The record of a stake transaction, with the stake delta decoded from
+    /// its stake AT cell on CKB.
+    #[method(name = "getStakeTransaction")]
+    async fn get_stake_transaction(
+        &self,
+        tx_hash: H256,
+    ) -> RpcResult<Option<StakeTransactionDetail>>;
+
+    /// The SMT amounts of each epoch in `start..=end`, zero for the epochs
+    /// without leaves.
+    #[method(name = "getEpochStakeAmounts")]
+    async fn get_epoch_stake_amounts(
+        &self,
+        operation_type: OperationType,
+        start: u32,
+        end: u32,
     ) -> RpcResult<Vec<StakeAmount>>;
 
+    /// The roots of the stake SMT of the epochs in `start..=end` which have
+    /// one, to follow the staker set without replaying the transactions.
+    #[method(name = "getSmtRoots")]
+    async fn get_smt_roots(&self, start: u64, end: u64) -> RpcResult<Vec<EpochRoot>>;
+
+    /// The page of the stakers by amount from the largest, without the zero
+    /// stakes and the excluded addresses. Pages count from 1.
     #[method(name = "getTopStakeAddress")]
     async fn get_top_stake_address(
         &self,
         page_number: u64,
         page_size: u64,
+        address_format: Option<AddressFormat>,
+        exclude: Option<Vec<Address>>,
+    ) -> RpcResult<Vec<AddressAmount>>;
+
+    /// The active validator set of the epoch, the `3 * quorum` stakers with
+    /// the largest amounts in the stake SMT, by amount.
+    #[method(name = "getValidatorSet")]
+    async fn get_validator_set(
+        &self,
+        epoch: u64,
+        address_format: Option<AddressFormat>,
+    ) -> RpcResult<Vec<AddressAmount>>;
+
+    /// The delegators of the staker with their delegated amounts, the added
+    /// amounts minus the redeemed ones.
+    #[method(name = "getStakerDelegators")]
+    async fn get_staker_delegators(
+        &self,
+        staker: Address,
+        address_format: Option<AddressFormat>,
     ) -> RpcResult<Vec<AddressAmount>>;
 
+    /// Whether the address is in the stake SMT of the epoch.
+    #[method(name = "isStaker")]
+    async fn is_staker(&self, address: Address, epoch: u64) -> RpcResult<bool>;
+
+    /// Whether the delegator is in the staker's delegate SMT of the epoch.
+    #[method(name = "isDelegator")]
+    async fn is_delegator(
+        &self,
+        staker: Address,
+        delegator: Address,
+        epoch: u64,
+    ) -> RpcResult<bool>;
+
+    /// How many more delegators the staker accepts in the epoch.
+    #[method(name = "getStakerDelegateCapacity")]
+    async fn get_staker_delegate_capacity(
+        &self,
+        staker: Address,
+        epoch: u64,
+    ) -> RpcResult<DelegateCapacity>;
+
     #[method(name = "getLatestStakeTransactions")]
     async fn get_latest_stake_transactions(
         &self,
@@ -75,6 +195,17 @@ pub trait AccountHistoryRpc {
 pub trait AxonStatusRpc {
     #[method(name = "getChainState")]
     async fn get_chain_state(&self) -> RpcResult<ChainState>;
+
+    #[method(name = "getNetworkType")]
+    async fn get_network_type(&self) -> RpcResult<NetworkType>;
+
+    /// The scripts and cell deps the transaction builders use.
+    #[method(name = "getScriptConfig")]
+    async fn get_script_config(&self) -> RpcResult<Vec<ScriptConfig>>;
+
+    /// Fails if the database doesn't answer, for readiness probes.
+    #[method(name = "systemHealth")]
+    async fn system_health(&self) -> RpcResult<()>;
 }
 
 #[rpc(server)]
@@ -113,21 +244,124 @@ pub trait OperationRpc {
     async fn send_transaction(&self, tx: Transaction) -> RpcResult<H256>;
 }
 
+/// The socket and response settings of the RPC server.
+#[derive(Clone, Debug)]
+pub struct ServerOptions {
+    /// The length of the queue of the connections waiting to be accepted.
+    pub backlog:         u32,
+    /// Send TCP keep-alive probes on idle connections, so that dead peers
+    /// don't hold connections.
+    pub tcp_keepalive:   bool,
+    pub max_connections: u32,
+    pub display:         AmountDisplay,
+    /// Serve HTTPS instead of plain HTTP.
+    pub tls:             Option<TlsOptions>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            backlog:         1024,
+            tcp_keepalive:   true,
+            max_connections: 100,
+            display:         AmountDisplay::default(),
+            tls:             None,
+        }
+    }
+}
+
+/// The handle of a running server, to stop it.
+#[derive(Clone, Debug)]
+pub enum RpcServerHandle {
+    Http(ServerHandle),
+    /// The HTTPS server stops when the channel fires or closes.
+    Https(Arc<watch::Sender<()>>),
+}
+
+impl RpcServerHandle {
+    pub fn stop(&self) -> Result<(), ApiError> {
+        match self {
+            RpcServerHandle::Http(handle) => handle
+                .stop()
+                .map_err(|e| ApiError::HttpServer(e.to_string())),
+            RpcServerHandle::Https(stop) => stop
+                .send(())
+                .map_err(|_| ApiError::HttpServer("the server is already stopped".to_owned())),
+        }
+    }
+}
+
+/// Start the server, returning its handle and the address it listens on, which
+/// tells the port picked for port 0.
 pub async fn run_server<Adapter: APIAdapter + 'static>(
     adapter: Arc<Adapter>,
     url: impl ToSocketAddrs,
-) -> Result<ServerHandle, ApiError> {
-    let mut module = StatusRpcModule::new(Arc::clone(&adapter)).into_rpc();
+    options: ServerOptions,
+) -> Result<(RpcServerHandle, SocketAddr), ApiError> {
+    let mut module = StatusRpcModule::new(Arc::clone(&adapter))
+        .with_display(options.display)
+        .into_rpc();
     let axon_rpc = AxonStatusRpc::new(Arc::clone(&adapter)).into_rpc();
     let op_rpc = OperationRpc::new(adapter).into_rpc();
-    module.merge(axon_rpc).unwrap();
-    module.merge(op_rpc).unwrap();
-    let server = ServerBuilder::new()
-        .http_only()
-        .build(url)
+    module
+        .merge(axon_rpc)
+        .map_err(|e| ApiError::HttpServer(e.to_string()))?;
+    module
+        .merge(op_rpc)
+        .map_err(|e| ApiError::HttpServer(e.to_string()))?;
+    let listener = listen(url, &options)
         .await
         .map_err(|e| ApiError::HttpServer(e.to_string()))?;
-    println!("addr: {:?}", server.local_addr().unwrap());
+    let addr = listener
+        .local_addr()
+        .map_err(|e| ApiError::HttpServer(e.to_string()))?;
+
+    let handle = match &options.tls {
+        Some(tls) => {
+            let acceptor = tls
+                .acceptor()
+                .map_err(|e| ApiError::HttpServer(e.to_string()))?;
+            let listener = tokio::net::TcpListener::from_std(listener)
+                .map_err(|e| ApiError::HttpServer(e.to_string()))?;
+            let (stop, stopped) = watch::channel(());
+            tokio::spawn(tls::serve(
+                listener,
+                acceptor,
+                module,
+                options.max_connections,
+                stopped,
+            ));
+            log::info!("RPC server listening on {} (tls)", addr);
+            RpcServerHandle::Https(Arc::new(stop))
+        }
+        None => {
+            let server = ServerBuilder::new()
+                .http_only()
+                .max_connections(options.max_connections)
+                .build_from_tcp(listener)
+                .map_err(|e| ApiError::HttpServer(e.to_string()))?;
+            let handle = server
+                .start(module)
+                .map_err(|e| ApiError::HttpServer(e.to_string()))?;
+            log::info!("RPC server listening on {}", addr);
+            RpcServerHandle::Http(handle)
+        }
+    };
+
+    Ok((handle, addr))
+}
 
-    Ok(server.start(module).unwrap())
+async fn listen(url: impl ToSocketAddrs, options: &ServerOptions) -> io::Result<TcpListener> {
+    let addr = lookup_host(url).await?.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::AddrNotAvailable, "no address to listen on")
+    })?;
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    socket.set_keepalive(options.tcp_keepalive)?;
+    socket.bind(addr)?;
+    socket.listen(options.backlog)?.into_std()
 }
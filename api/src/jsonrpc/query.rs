@@ -1,4 +1,8 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::Arc,
+};
 
 use crate::{
     error::ApiError,
@@ -8,49 +12,301 @@ use common::{
     traits::api::APIAdapter,
     types::{
         api::{
-            AddressAmount, ChainState, HistoryEvent, HistoryTransactions, OperationStatus,
-            OperationType, RewardFrom, RewardHistory, RewardState, StakeAmount, StakeHistory,
-            StakeRate, StakeState, StakeTransaction,
+            AccountSummary, AddressAmount, AddressFormat, AmountSource, ChainState, Cursor,
+            DelegateCapacity, EpochHistory, EpochRoot, HistoryEvent, HistoryTransactions,
+            OperationStatus, OperationType, RewardFrom, RewardHistory, RewardState, ScriptConfig,
+            SortOrder, StakeAmount, StakeDelta, StakeHistory, StakeRate, StakeState,
+            StakeTransaction, StakeTransactionDetail,
         },
-        smt::Address,
+        relation_db::transaction::Model,
+        smt::{Address, Amount},
+        tx_builder::NetworkType,
+        H256,
+    },
+    utils::{
+        convert::{to_ckb_h160, to_hex_address},
+        round::AmountDisplay,
     },
 };
 use jsonrpsee::{
     core::{async_trait, RpcResult},
     types::{error::INVALID_PARAMS_CODE, ErrorObjectOwned},
 };
+use tokio::task::JoinSet;
+use tx_builder::ckb::{
+    helper::{script_configs, OmniEth, Stake},
+    NETWORK_TYPE,
+};
+
+/// The most epochs read by one `getEpochStakeAmounts` call.
+const MAX_EPOCH_RANGE: u32 = 1000;
+/// The most SMT reads of `getEpochStakeAmounts` in flight at once.
+const EPOCH_READ_CONCURRENCY: usize = 8;
+/// The most addresses of one `getRewardStates` call.
+const MAX_REWARD_ADDRESSES: usize = 100;
 
 pub struct StatusRpcModule<Adapter> {
     adapter: Arc<Adapter>,
+    display: AmountDisplay,
 }
 
 impl<Adapter: APIAdapter> StatusRpcModule<Adapter> {
     #[allow(dead_code)]
     pub fn new(adapter: Arc<Adapter>) -> Self {
-        Self { adapter }
+        Self {
+            adapter,
+            display: AmountDisplay::default(),
+        }
+    }
+
+    pub fn with_display(mut self, display: AmountDisplay) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// A page of the records of an operation of the address, after the cursor
+    /// if one is given and by the page number otherwise.
+    async fn operation_history_page(
+        &self,
+        addr: Address,
+        operation: u32,
+        page_number: u64,
+        page_size: u64,
+        after: Option<Cursor>,
+        order: SortOrder,
+    ) -> RpcResult<Vec<Model>> {
+        let res = match after {
+            Some(cursor) => {
+                let id = cursor.id().ok_or(ErrorObjectOwned::owned(
+                    INVALID_PARAMS_CODE,
+                    "invalid cursor".to_string(),
+                    None::<()>,
+                ))?;
+                self.adapter
+                    .get_operation_history_after(addr, operation, order, Some(id), page_size)
+                    .await
+            }
+            None => {
+                let offset = page_offset(page_number, page_size)?;
+                self.adapter
+                    .get_operation_history(addr, operation, order, offset, page_size)
+                    .await
+            }
+        }
+        .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        Ok(res)
+    }
+}
+
+fn format_address(addr: &Address, format: AddressFormat) -> String {
+    match format {
+        AddressFormat::Hex => to_hex_address(addr),
+        AddressFormat::Ckb => OmniEth::address_of(&to_ckb_h160(addr), &NETWORK_TYPE.load()),
+    }
+}
+
+/// Format an address read back from the relation database, which keeps the
+/// full hex of it.
+fn format_stored_address(stored: &str, format: AddressFormat) -> Result<String, ApiError> {
+    stored
+        .trim_start_matches("0x")
+        .parse::<Address>()
+        .map(|addr| format_address(&addr, format))
+        .map_err(|e| ApiError::Adapter(format!("invalid stored address {}: {}", stored, e)))
+}
+
+/// The offset of a page, rejecting empty pages and pages out of the range of
+/// the database.
+pub(crate) fn page_offset(page_number: u64, page_size: u64) -> RpcResult<u64> {
+    page_number
+        .checked_sub(1)
+        .filter(|_| page_size > 0)
+        .and_then(|n| n.checked_mul(page_size))
+        .filter(|offset| {
+            offset
+                .checked_add(page_size)
+                .map_or(false, |end| end <= i64::MAX as u64)
+        })
+        .ok_or(ErrorObjectOwned::owned(
+            INVALID_PARAMS_CODE,
+            "invalid page".to_string(),
+            None::<()>,
+        ))
+}
+
+/// Run `f` over the items with at most `limit` of them in flight, returning the
+/// results in the order of the items. The first error cancels the rest.
+pub(crate) async fn bounded_join<I, T, F, Fut>(
+    items: Vec<I>,
+    limit: usize,
+    f: F,
+) -> common::Result<Vec<T>>
+where
+    F: Fn(I) -> Fut,
+    Fut: Future<Output = common::Result<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let limit = limit.max(1);
+    let mut results = Vec::with_capacity(items.len());
+    results.resize_with(items.len(), || None);
+    let mut tasks = JoinSet::new();
+
+    for (idx, item) in items.into_iter().enumerate() {
+        if tasks.len() >= limit {
+            let (done, res) = tasks.join_next().await.expect("a task is in flight")?;
+            results[done] = Some(res?);
+        }
+        let fut = f(item);
+        tasks.spawn(async move { (idx, fut.await) });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        let (done, res) = joined?;
+        results[done] = Some(res?);
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|res| res.expect("every task is joined"))
+        .collect())
+}
+
+/// The stake rate of an address, taken from its latest record.
+fn stake_rate(
+    addr: &Address,
+    latest: Option<&Model>,
+    format: AddressFormat,
+    display: &AmountDisplay,
+) -> Option<StakeRate> {
+    latest.map(|s| {
+        let (stake_amount, delegate_amount) = (s.stake_amount as u64, s.delegate_amount as u64);
+        let (stake_rate_bps, delegate_rate_bps) =
+            StakeRate::basis_points(stake_amount, delegate_amount);
+        StakeRate {
+            address: format_address(addr, format),
+            stake_rate: s.stake_rate.clone(),
+            delegate_rate: s.delegate_rate.clone(),
+            stake_rate_bps,
+            delegate_rate_bps,
+            stake_ratio: display.ratio(stake_amount, stake_amount + delegate_amount),
+            delegate_ratio: display.ratio(delegate_amount, stake_amount + delegate_amount),
+        }
+    })
+}
+
+fn stake_state(records: &[Model]) -> StakeState {
+    let (stake_amount, amount, delegate_amount, withdrawable_amount) =
+        records.iter().fold((0, 0, 0, 0), |res, model| {
+            if model.operation == OperationType::Stake as u32 {
+                (
+                    res.0 + model.total_amount,
+                    res.1,
+                    res.2 + model.delegate_amount,
+                    res.3 + model.withdrawable_amount,
+                )
+            } else if model.operation == OperationType::Delegate as u32 {
+                (
+                    res.0,
+                    res.1 + model.total_amount,
+                    res.2 + model.delegate_amount,
+                    res.3 + model.withdrawable_amount,
+                )
+            } else {
+                res
+            }
+        });
+    StakeState {
+        total_amount: amount,
+        stake_amount,
+        delegate_amount,
+        withdrawable_amount,
+        exists: !records.is_empty(),
+    }
+}
+
+fn history_record(model: Model) -> Result<EpochHistory, ApiError> {
+    let hash = model
+        .tx_hash
+        .trim_start_matches("0x")
+        .parse()
+        .map_err(|e| ApiError::Adapter(format!("invalid tx hash {}: {}", model.tx_hash, e)))?;
+    Ok(EpochHistory {
+        address:     model.address,
+        operation:   OperationType::from(model.operation),
+        event:       HistoryEvent::from(model.event),
+        amount:      model.total_amount,
+        transaction: HistoryTransactions {
+            hash,
+            status: OperationStatus::from(model.status),
+            timestamp: model.timestamp as u64,
+        },
+    })
+}
+
+fn reward_state(records: &[Model]) -> RewardState {
+    let (lock_amount, unlock_amount) = records.iter().fold((0, 0), |res, model| {
+        if model.operation == OperationType::Stake as u32 {
+            (res.0 + model.epoch, res.1)
+        } else if model.operation == OperationType::Delegate as u32 {
+            (res.0, res.1 + model.epoch)
+        } else {
+            res
+        }
+    });
+    RewardState {
+        lock_amount,
+        unlock_amount,
+        exists: !records.is_empty(),
+    }
+}
+
+/// The `size` stakers with the largest amounts, by amount and then by address
+/// for the equal amounts.
+fn top_stakers(stakers: HashMap<Address, Amount>, size: usize) -> Vec<(Address, Amount)> {
+    let mut stakers = stakers.into_iter().collect::<Vec<_>>();
+    stakers.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    stakers.truncate(size);
+    stakers
+}
+
+/// The delegated amount of each delegator, skipping the fully redeemed ones.
+fn delegator_amounts(records: &[Model]) -> BTreeMap<String, u32> {
+    let mut amounts = BTreeMap::new();
+    for model in records.iter() {
+        let amount: &mut u32 = amounts.entry(model.address.clone()).or_default();
+        *amount = if model.event == HistoryEvent::Redeem as u32 {
+            amount.saturating_sub(model.total_amount)
+        } else {
+            amount.saturating_add(model.total_amount)
+        };
     }
+    amounts.retain(|_, amount| *amount > 0);
+    amounts
 }
 
 #[async_trait]
 impl<Adapter: APIAdapter + 'static> AccountHistoryRpcServer for StatusRpcModule<Adapter> {
-    async fn get_stake_rate(&self, addr: Address) -> RpcResult<StakeRate> {
+    async fn get_stake_rate(
+        &self,
+        addr: Address,
+        address_format: Option<AddressFormat>,
+    ) -> RpcResult<StakeRate> {
         let res = self
             .adapter
-            .get_records_by_address(addr, 0, 1)
+            .get_records_by_address(addr, SortOrder::Desc, 0, 1)
             .await
             .map_err(|e| ApiError::Adapter(e.to_string()))?;
 
-        res.get(0)
-            .map(|s| StakeRate {
-                address:       addr.to_string(),
-                stake_rate:    s.stake_rate.clone(),
-                delegate_rate: s.delegate_rate.clone(),
-            })
-            .ok_or(ErrorObjectOwned::owned(
-                INVALID_PARAMS_CODE,
-                "wrong number of arguments".to_string(),
-                None::<()>,
-            ))
+        stake_rate(
+            &addr,
+            res.first(),
+            address_format.unwrap_or_default(),
+            &self.display,
+        )
+        .ok_or(ErrorObjectOwned::owned(
+            INVALID_PARAMS_CODE,
+            "wrong number of arguments".to_string(),
+            None::<()>,
+        ))
     }
 
     async fn get_stake_state(&self, addr: Address) -> RpcResult<StakeState> {
@@ -59,55 +315,74 @@ impl<Adapter: APIAdapter + 'static> AccountHistoryRpcServer for StatusRpcModule<
             .get_address_state(addr)
             .await
             .map_err(|e| ApiError::Adapter(e.to_string()))?;
-        let (stake_amount, amount, delegate_amount, withdrawable_amount) =
-            res.iter().fold((0, 0, 0, 0), |res, model| {
-                if model.operation == OperationType::Stake as u32 {
-                    (
-                        res.0 + model.total_amount,
-                        res.1,
-                        res.2 + model.delegate_amount,
-                        res.3 + model.withdrawable_amount,
-                    )
-                } else if model.operation == OperationType::Delegate as u32 {
-                    (
-                        res.0,
-                        res.1 + model.total_amount,
-                        res.2 + model.delegate_amount,
-                        res.3 + model.withdrawable_amount,
-                    )
-                } else {
-                    res
-                }
-            });
-        let res = StakeState {
-            total_amount: amount,
-            stake_amount,
-            delegate_amount,
-            withdrawable_amount,
-        };
-        Ok(res)
+        Ok(stake_state(&res))
+    }
+
+    async fn get_account_summary(
+        &self,
+        addr: Address,
+        address_format: Option<AddressFormat>,
+    ) -> RpcResult<AccountSummary> {
+        // Read the records once, so that a write in between can't make the rate
+        // and the state disagree.
+        let res = self
+            .adapter
+            .get_address_state(addr)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        Ok(AccountSummary {
+            stake_rate:  stake_rate(
+                &addr,
+                res.last(),
+                address_format.unwrap_or_default(),
+                &self.display,
+            ),
+            stake_state: stake_state(&res),
+        })
     }
 
     async fn get_reward_state(&self, addr: Address) -> RpcResult<RewardState> {
         let res = self
             .adapter
-            .get_records_by_address(addr, 0, 1)
+            .get_address_state(addr)
             .await
             .map_err(|e| ApiError::Adapter(e.to_string()))?;
-        let (lock_reward_amount, unlock_reward_amount) = res.iter().fold((0, 0), |res, model| {
-            if model.operation == OperationType::Stake as u32 {
-                (res.0 + model.epoch, res.1)
-            } else if model.operation == OperationType::Delegate as u32 {
-                (res.0, res.1 + model.epoch)
-            } else {
-                res
-            }
-        });
-        let res = RewardState {
-            lock_amount:   lock_reward_amount,
-            unlock_amount: unlock_reward_amount,
-        };
-        Ok(res)
+        Ok(reward_state(&res))
+    }
+
+    async fn get_reward_states(&self, addrs: Vec<Address>) -> RpcResult<Vec<RewardState>> {
+        if addrs.len() > MAX_REWARD_ADDRESSES {
+            return Err(ErrorObjectOwned::owned(
+                INVALID_PARAMS_CODE,
+                format!("too many addresses, at most {}", MAX_REWARD_ADDRESSES),
+                None::<()>,
+            ));
+        }
+
+        let res = self
+            .adapter
+            .get_records_by_addresses(addrs.clone())
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+
+        let mut records: HashMap<String, Vec<Model>> = HashMap::new();
+        for model in res {
+            records
+                .entry(model.address.clone())
+                .or_default()
+                .push(model);
+        }
+
+        let states = addrs
+            .iter()
+            .map(|addr| {
+                records
+                    .get(&to_hex_address(addr))
+                    .map(|res| reward_state(res.as_slice()))
+                    .unwrap_or_default()
+            })
+            .collect();
+        Ok(states)
     }
 
     async fn get_stake_history(
@@ -117,14 +392,19 @@ impl<Adapter: APIAdapter + 'static> AccountHistoryRpcServer for StatusRpcModule<
         page_size: u64,
         event: HistoryEvent,
         history_type: OperationType,
+        after: Option<Cursor>,
+        order: Option<SortOrder>,
     ) -> RpcResult<Vec<StakeHistory>> {
-        let offset = (page_number - 1) * page_size;
-        let history_type = history_type as u32;
         let res = self
-            .adapter
-            .get_operation_history(addr, history_type, offset, page_size)
-            .await
-            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+            .operation_history_page(
+                addr,
+                history_type as u32,
+                page_number,
+                page_size,
+                after,
+                order.unwrap_or_default(),
+            )
+            .await?;
         let event_type = event as u32;
 
         let txs = res.iter().filter(|m| m.event == event_type).cloned().fold(
@@ -145,11 +425,14 @@ impl<Adapter: APIAdapter + 'static> AccountHistoryRpcServer for StatusRpcModule<
             .filter(|m| m.event == event_type)
             .cloned()
             .map(|model| StakeHistory {
-                id: addr.to_string(),
+                id: to_hex_address(&addr),
                 amount: model.total_amount,
+                epoch: model.epoch,
+                inauguration_epoch: model.inauguration_epoch,
                 event,
                 status: OperationStatus::from(model.status),
                 transactions: txs.clone(),
+                cursor: Cursor::new(model.id),
             })
             .collect();
         Ok(reses)
@@ -160,15 +443,21 @@ impl<Adapter: APIAdapter + 'static> AccountHistoryRpcServer for StatusRpcModule<
         addr: Address,
         page_number: u64,
         page_size: u64,
-    ) -> RpcResult<RewardHistory> {
-        let offset = (page_number - 1) * page_size;
-        let reward_type = OperationType::Reward as u32;
+        after: Option<Cursor>,
+        order: Option<SortOrder>,
+    ) -> RpcResult<Vec<RewardHistory>> {
         let res = self
-            .adapter
-            .get_operation_history(addr, reward_type, offset, page_size)
-            .await
-            .map_err(|e| ApiError::Adapter(e.to_string()))?;
-        res.get(0)
+            .operation_history_page(
+                addr,
+                OperationType::Reward as u32,
+                page_number,
+                page_size,
+                after,
+                order.unwrap_or(SortOrder::Asc),
+            )
+            .await?;
+        let res = res
+            .into_iter()
             .map(|s| RewardHistory {
                 epoch:  s.epoch,
                 amount: s.total_amount,
@@ -178,12 +467,10 @@ impl<Adapter: APIAdapter + 'static> AccountHistoryRpcServer for StatusRpcModule<
                     address:     addr,
                     amount:      s.total_amount as u64,
                 },
+                cursor: Cursor::new(s.id),
             })
-            .ok_or(ErrorObjectOwned::owned(
-                INVALID_PARAMS_CODE,
-                "wrong number of arguments".to_string(),
-                None::<()>,
-            ))
+            .collect();
+        Ok(res)
     }
 
     async fn get_stake_amount_by_epoch(
@@ -191,51 +478,304 @@ impl<Adapter: APIAdapter + 'static> AccountHistoryRpcServer for StatusRpcModule<
         operation_type: OperationType,
         page_number: u64,
         page_size: u64,
+        source: Option<AmountSource>,
+    ) -> RpcResult<Vec<StakeAmount>> {
+        let offset = page_offset(page_number, page_size)?;
+        let operation = operation_type as u32;
+
+        if source.unwrap_or_default() == AmountSource::History {
+            let res = self
+                .adapter
+                .get_stake_amount_by_epoch(operation, offset, page_size)
+                .await
+                .map_err(|e| ApiError::Adapter(e.to_string()))?
+                .into_iter()
+                .map(|model| StakeAmount {
+                    epoch:  model.epoch,
+                    amount: model.total_amount.to_string(),
+                })
+                .collect();
+            return Ok(res);
+        }
+
+        // The SMT keeps the balances of stakes and delegations only, and the
+        // page is over the epochs, one entry per epoch with a stake root.
+        if operation == OperationType::Reward as u32 || page_size > MAX_EPOCH_RANGE as u64 {
+            return Err(ErrorObjectOwned::owned(
+                INVALID_PARAMS_CODE,
+                "invalid operation or page size for the SMT source".to_string(),
+                None::<()>,
+            ));
+        }
+        let epochs = self
+            .adapter
+            .get_smt_roots(offset, offset + page_size - 1)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?
+            .into_iter()
+            .map(|(epoch, _)| epoch)
+            .collect::<Vec<_>>();
+        let amounts = bounded_join(epochs.clone(), EPOCH_READ_CONCURRENCY, |epoch| {
+            let adapter = Arc::clone(&self.adapter);
+            async move { adapter.get_smt_amount_by_epoch(operation, epoch).await }
+        })
+        .await
+        .map_err(|e| ApiError::Adapter(e.to_string()))?;
+
+        let res = epochs
+            .into_iter()
+            .zip(amounts)
+            .map(|(epoch, amount)| {
+                let epoch = u32::try_from(epoch).map_err(|_| {
+                    ApiError::Adapter(format!("epoch {} of the SMT is out of range", epoch))
+                })?;
+                Ok(StakeAmount {
+                    epoch,
+                    amount: amount.unwrap_or_default().to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        Ok(res)
+    }
+
+    async fn get_epoch_stake_amounts(
+        &self,
+        operation_type: OperationType,
+        start: u32,
+        end: u32,
     ) -> RpcResult<Vec<StakeAmount>> {
-        let offset = (page_number - 1) * page_size;
+        if end < start || end - start >= MAX_EPOCH_RANGE {
+            return Err(ErrorObjectOwned::owned(
+                INVALID_PARAMS_CODE,
+                "invalid epoch range".to_string(),
+                None::<()>,
+            ));
+        }
+
+        let operation = operation_type as u32;
+        let epochs = (start..=end).collect::<Vec<_>>();
+        let amounts = bounded_join(epochs.clone(), EPOCH_READ_CONCURRENCY, |epoch| {
+            let adapter = Arc::clone(&self.adapter);
+            async move {
+                adapter
+                    .get_smt_amount_by_epoch(operation, epoch as u64)
+                    .await
+            }
+        })
+        .await
+        .map_err(|e| ApiError::Adapter(e.to_string()))?;
+
+        Ok(epochs
+            .into_iter()
+            .zip(amounts)
+            .map(|(epoch, amount)| StakeAmount {
+                epoch,
+                amount: amount.unwrap_or_default().to_string(),
+            })
+            .collect())
+    }
+
+    async fn get_smt_roots(&self, start: u64, end: u64) -> RpcResult<Vec<EpochRoot>> {
+        if end < start || end - start >= MAX_EPOCH_RANGE as u64 {
+            return Err(ErrorObjectOwned::owned(
+                INVALID_PARAMS_CODE,
+                "invalid epoch range".to_string(),
+                None::<()>,
+            ));
+        }
+
+        let roots = self
+            .adapter
+            .get_smt_roots(start, end)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        Ok(roots
+            .into_iter()
+            .map(|(epoch, root)| EpochRoot {
+                epoch,
+                root: ckb_types::H256::from_slice(root.as_slice()).expect("32 bytes root"),
+            })
+            .collect())
+    }
+
+    async fn get_epoch_history(
+        &self,
+        epoch: u32,
+        operation_type: Option<OperationType>,
+        page_number: u64,
+        page_size: u64,
+    ) -> RpcResult<Vec<EpochHistory>> {
+        let offset = page_offset(page_number, page_size)?;
         let res = self
             .adapter
-            .get_stake_amount_by_epoch(operation_type as u32, offset, page_size)
+            .get_history_by_epoch(epoch, operation_type.map(|t| t as u32), offset, page_size)
             .await
             .map_err(|e| ApiError::Adapter(e.to_string()))?;
-        let res: Vec<StakeAmount> = res
+
+        Ok(res
             .into_iter()
-            .map(|model| StakeAmount {
-                epoch:  model.epoch,
-                amount: model.total_amount.to_string(),
+            .map(history_record)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_stake_transaction(
+        &self,
+        tx_hash: H256,
+    ) -> RpcResult<Option<StakeTransactionDetail>> {
+        let record = match self
+            .adapter
+            .get_record_by_tx_hash(tx_hash)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?
+        {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        let stake_delta = self
+            .adapter
+            .get_ckb_transaction(tx_hash)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?
+            .and_then(|tx| {
+                tx.outputs_with_data_iter()
+                    .find_map(|(output, data)| Stake::at_cell_item(&output, &data))
             })
-            .collect();
-        Ok(res)
+            .map(StakeDelta::from);
+
+        Ok(Some(StakeTransactionDetail {
+            record: history_record(record)?,
+            stake_delta,
+        }))
     }
 
     async fn get_top_stake_address(
         &self,
         page_number: u64,
         page_size: u64,
+        address_format: Option<AddressFormat>,
+        exclude: Option<Vec<Address>>,
     ) -> RpcResult<Vec<AddressAmount>> {
-        let total_num = page_number * page_size;
+        let address_format = address_format.unwrap_or_default();
+        let offset = page_offset(page_number, page_size)?;
         let res = self
             .adapter
-            .get_top_stake_address(OperationType::Stake as u32)
+            .get_top_stake_address(
+                OperationType::Stake as u32,
+                exclude.unwrap_or_default(),
+                offset,
+                page_size,
+            )
             .await
             .map_err(|e| ApiError::Adapter(e.to_string()))?;
-        let res: Vec<AddressAmount> = res
+        let res = res
             .iter()
-            .take(total_num as usize)
-            .map(|m| AddressAmount {
-                address: m.address.clone(),
-                amount:  m.total_amount.to_string(),
+            .map(|m| {
+                Ok(AddressAmount {
+                    address: format_stored_address(&m.address, address_format)?,
+                    amount:  m.total_amount.to_string(),
+                })
             })
-            .collect();
+            .collect::<Result<Vec<_>, ApiError>>()?;
         Ok(res)
     }
 
+    async fn get_validator_set(
+        &self,
+        epoch: u64,
+        address_format: Option<AddressFormat>,
+    ) -> RpcResult<Vec<AddressAmount>> {
+        let address_format = address_format.unwrap_or_default();
+        let quorum = self
+            .adapter
+            .get_quorum()
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        let stakers = self
+            .adapter
+            .get_stakers(epoch)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        Ok(top_stakers(stakers, 3 * quorum as usize)
+            .into_iter()
+            .map(|(address, amount)| AddressAmount {
+                address: format_address(&address, address_format),
+                amount:  amount.to_string(),
+            })
+            .collect())
+    }
+
+    async fn get_staker_delegators(
+        &self,
+        staker: Address,
+        address_format: Option<AddressFormat>,
+    ) -> RpcResult<Vec<AddressAmount>> {
+        let address_format = address_format.unwrap_or_default();
+        let res = self
+            .adapter
+            .get_delegators_of_staker(staker)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        let res = delegator_amounts(&res)
+            .into_iter()
+            .map(|(address, amount)| {
+                Ok(AddressAmount {
+                    address: format_stored_address(&address, address_format)?,
+                    amount:  amount.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        Ok(res)
+    }
+
+    async fn is_staker(&self, address: Address, epoch: u64) -> RpcResult<bool> {
+        let is_staker = self
+            .adapter
+            .is_staker(epoch, address)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        Ok(is_staker)
+    }
+
+    async fn is_delegator(
+        &self,
+        staker: Address,
+        delegator: Address,
+        epoch: u64,
+    ) -> RpcResult<bool> {
+        let is_delegator = self
+            .adapter
+            .is_delegator(epoch, staker, delegator)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        Ok(is_delegator)
+    }
+
+    async fn get_staker_delegate_capacity(
+        &self,
+        staker: Address,
+        epoch: u64,
+    ) -> RpcResult<DelegateCapacity> {
+        let max_delegator_size = self
+            .adapter
+            .get_maximum_delegators(staker)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        let delegator_count = self
+            .adapter
+            .get_delegator_count(epoch, staker)
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+
+        Ok(DelegateCapacity::new(max_delegator_size, delegator_count))
+    }
+
     async fn get_latest_stake_transactions(
         &self,
         page_number: u64,
         page_size: u64,
     ) -> RpcResult<Vec<StakeTransaction>> {
-        let offset = (page_number - 1) * page_size;
+        let offset = page_offset(page_number, page_size)?;
         let res = self
             .adapter
             .get_latest_stake_transactions(offset, page_size)
@@ -275,4 +815,21 @@ impl<Adapter: APIAdapter + 'static> AxonStatusRpcServer for AxonStatusRpc<Adapte
         // ChainState::default();
         Ok(res)
     }
+
+    async fn get_network_type(&self) -> RpcResult<NetworkType> {
+        // Load on each call, the network type may be set after the server starts.
+        Ok((**NETWORK_TYPE.load()).clone())
+    }
+
+    async fn get_script_config(&self) -> RpcResult<Vec<ScriptConfig>> {
+        Ok(script_configs())
+    }
+
+    async fn system_health(&self) -> RpcResult<()> {
+        self.adapter
+            .ping()
+            .await
+            .map_err(|e| ApiError::Adapter(e.to_string()))?;
+        Ok(())
+    }
 }
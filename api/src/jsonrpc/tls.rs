@@ -0,0 +1,182 @@
+use std::{
+    convert::Infallible,
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use jsonrpsee::RpcModule;
+use rustls_pemfile::Item;
+use serde_json::Value;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Semaphore};
+use tokio::time::timeout;
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// How long a client has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// The limit of the body of a request, the default of the plain server.
+const MAX_REQUEST_BODY_SIZE: usize = 10 * 1024 * 1024;
+const PARSE_ERROR: &str =
+    r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error"},"id":null}"#;
+const INVALID_REQUEST: &str =
+    r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}"#;
+
+/// The PEM files of the certificate chain and the private key, to serve the
+/// RPC over HTTPS.
+#[derive(Clone, Debug)]
+pub struct TlsOptions {
+    pub cert_path: PathBuf,
+    pub key_path:  PathBuf,
+}
+
+impl TlsOptions {
+    pub(crate) fn acceptor(&self) -> io::Result<TlsAcceptor> {
+        let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&self.cert_path)?))?
+            .into_iter()
+            .map(Certificate)
+            .collect();
+        let key = read_key(&self.key_path)?;
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// The first PKCS#8, RSA or SEC1 private key in the file.
+fn read_key(path: &Path) -> io::Result<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => {
+                return Ok(PrivateKey(key))
+            }
+            _ => continue,
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("no private key in {}", path.display()),
+    ))
+}
+
+/// Accept the TLS connections on `listener` and serve the HTTP requests on
+/// them with the methods of the module, until `stop` fires. The jsonrpsee
+/// server can only serve plain TCP, so the requests are run in process rather
+/// than forwarded to a plain listener any local process could reach.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    module: RpcModule<()>,
+    max_connections: u32,
+    mut stop: watch::Receiver<()>,
+) {
+    let connections = Arc::new(Semaphore::new(max_connections as usize));
+    loop {
+        let (stream, peer) = tokio::select! {
+            res = listener.accept() => match res {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    log::error!("Failed to accept a TLS connection: {}", e);
+                    continue;
+                }
+            },
+            _ = stop.changed() => return,
+        };
+        let permit = match Arc::clone(&connections).try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                log::warn!("Too many connections, refusing the one of {}", peer);
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let module = module.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, acceptor, module).await {
+                log::debug!("TLS connection of {} failed: {}", peer, e);
+            }
+            drop(permit);
+        });
+    }
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    acceptor: TlsAcceptor,
+    module: RpcModule<()>,
+) -> io::Result<()> {
+    let tls = timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))??;
+    let service = service_fn(move |req| respond(module.clone(), req));
+    Http::new()
+        .http1_only(true)
+        .serve_connection(tls, service)
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
+
+async fn respond(module: RpcModule<()>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::POST {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+    let body = match read_body(req.into_body()).await {
+        Some(body) => body,
+        None => return Ok(status(StatusCode::PAYLOAD_TOO_LARGE)),
+    };
+    let body = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(requests)) => {
+            let mut responses = Vec::with_capacity(requests.len());
+            for request in requests {
+                responses.push(call(&module, &request.to_string()).await);
+            }
+            format!("[{}]", responses.join(","))
+        }
+        Ok(request) => call(&module, &request.to_string()).await,
+        Err(_) => PARSE_ERROR.to_owned(),
+    };
+    let mut response = Response::new(Body::from(body));
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Ok(response)
+}
+
+/// Run one request, the way the plain server does.
+async fn call(module: &RpcModule<()>, request: &str) -> String {
+    match module.raw_json_request(request, 1).await {
+        Ok((response, _)) => response.result,
+        Err(_) => INVALID_REQUEST.to_owned(),
+    }
+}
+
+/// The body, or `None` if it is larger than the plain server takes.
+async fn read_body(mut body: Body) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.ok()?;
+        if buf.len() + chunk.len() > MAX_REQUEST_BODY_SIZE {
+            return None;
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Some(buf)
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = code;
+    response
+}
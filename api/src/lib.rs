@@ -5,4 +5,4 @@ mod jsonrpc;
 mod tests;
 
 pub use adapter::DefaultAPIAdapter;
-pub use jsonrpc::run_server;
+pub use jsonrpc::{run_server, RpcServerHandle, ServerOptions, TlsOptions};
@@ -1,22 +1,79 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
-use crate::{adapter::DefaultAPIAdapter, jsonrpc::run_server};
+use crate::{
+    adapter::DefaultAPIAdapter,
+    jsonrpc::{
+        query::{bounded_join, page_offset, AxonStatusRpc, StatusRpcModule},
+        run_server, AccountHistoryRpcServer, AxonStatusRpcServer, ServerOptions, TlsOptions,
+    },
+};
+use ckb_types::{
+    core::{ScriptHashType, TransactionBuilder},
+    packed::{CellOutput, Script},
+    prelude::{Builder, Entity, Pack},
+};
 use common::{
-    traits::query::TransactionStorage,
-    types::{relation_db::transaction, H160},
+    traits::{
+        api::APIAdapter,
+        query::TransactionStorage,
+        smt::{DelegateSmtStorage, StakeSmtStorage},
+    },
+    types::{
+        api::{
+            AccountSummary, AddressAmount, AddressFormat, AmountSource, Cursor, EpochRoot,
+            HistoryEvent, OperationType, RewardHistory, RewardState, ScriptConfig, SortOrder,
+            StakeAmount, StakeDelta, StakeHistory, StakeRate, StakeState, StakeTransactionDetail,
+        },
+        axon_types::{
+            metadata::{Metadata, MetadataCellData, MetadataList},
+            stake::{StakeAtCellData, StakeAtCellLockData},
+        },
+        relation_db::transaction,
+        smt::UserAmount,
+        tx_builder::{NetworkType, ScriptKind, StakeItem},
+        H160, H256,
+    },
+    utils::convert::{to_ckb_h256, to_hex_address, to_uint16},
     AnyError, Result,
 };
+use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder, rpc_params, RpcModule};
 use storage::{
     relation_db::{establish_connection, Set, TransactionHistory},
     smt::SmtManager,
 };
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+use tx_builder::ckb::define::constants::INAUGURATION;
+use tx_builder::ckb::define::scripts::STAKE_LOCK_TESTNET;
+use tx_builder::ckb::helper::token_cell_data;
+use tx_builder::ckb::mock::MockCkbRpc;
+use tx_builder::set_network_type;
 
 static RELATION_DB_URL: &str = "sqlite::memory:";
 static ROCKS_DB_PATH: &str = "./free-space/smt";
+// A self-signed certificate for localhost.
+static TLS_CERT_PATH: &str = "./src/tests/tls_cert.pem";
+static TLS_KEY_PATH: &str = "./src/tests/tls_key.pem";
+
+lazy_static::lazy_static! {
+    /// The network type is global, the tests which set or depend on it take
+    /// turns.
+    static ref NETWORK_TYPE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
 
 pub async fn mock_data(hash: String, amount: u32) -> Result<transaction::ActiveModel, AnyError> {
     Ok(transaction::ActiveModel {
-        address: Set(H160::zero().to_string()),
+        address: Set(to_hex_address(&H160::zero())),
         timestamp: Set(1),
         operation: Set(1),
         event: Set(1),
@@ -33,13 +90,44 @@ pub async fn mock_data(hash: String, amount: u32) -> Result<transaction::ActiveM
     })
 }
 
+type MockAdapter = DefaultAPIAdapter<TransactionHistory, SmtManager, MockCkbRpc>;
+
+/// An adapter over a fresh database and an SMT of its own for each test, empty
+/// at the start of every run, with a writer on the database and the SMT.
+async fn adapter(
+    name: &str,
+    ckb_rpc: MockCkbRpc,
+) -> (TransactionHistory, Arc<SmtManager>, MockAdapter) {
+    let db = establish_connection(RELATION_DB_URL).await.unwrap();
+    let mut smt_path = PathBuf::from(ROCKS_DB_PATH);
+    smt_path.push(name);
+    let _ = fs::remove_dir_all(&smt_path);
+    let smt_manager = Arc::new(SmtManager::new(smt_path));
+    let adapter = DefaultAPIAdapter::new(
+        Arc::new(TransactionHistory { db: db.clone() }),
+        Arc::clone(&smt_manager),
+        Arc::new(ckb_rpc),
+    );
+    (TransactionHistory { db }, smt_manager, adapter)
+}
+
+/// The query module of the test, with a writer on its database.
+async fn module(name: &str) -> (TransactionHistory, RpcModule<StatusRpcModule<MockAdapter>>) {
+    let (writer, _, adapter) = adapter(name, MockCkbRpc::default()).await;
+    (writer, StatusRpcModule::new(Arc::new(adapter)).into_rpc())
+}
+
 async fn _mock_adapter() {
     let db = establish_connection(RELATION_DB_URL).await.unwrap();
     let relation_db = TransactionHistory { db };
     let mut smt_path = PathBuf::from(ROCKS_DB_PATH);
     smt_path.push("stake");
     let smt_manager = SmtManager::new(smt_path);
-    let _adapter = DefaultAPIAdapter::new(Arc::new(relation_db), Arc::new(smt_manager));
+    let _adapter = DefaultAPIAdapter::new(
+        Arc::new(relation_db),
+        Arc::new(smt_manager),
+        Arc::new(MockCkbRpc::default()),
+    );
 }
 
 #[tokio::test]
@@ -50,7 +138,7 @@ async fn mock_db() {
     relation_db1.insert(data0).await.unwrap();
     relation_db1.insert(data1).await.unwrap();
     let res = relation_db1
-        .get_records_by_address(H160::zero(), 0, 4)
+        .get_records_by_address(H160::zero(), SortOrder::Asc, 0, 4)
         .await;
     println!("{:?}", res);
 }
@@ -62,8 +150,817 @@ async fn mock_jsonrpc_server() -> Result<()> {
     let mut smt_path = PathBuf::from(ROCKS_DB_PATH);
     smt_path.push("stake");
     let smt_manager = SmtManager::new(smt_path);
-    let adapter = DefaultAPIAdapter::new(Arc::new(relation_db), Arc::new(smt_manager));
-    let _ = run_server(Arc::new(adapter), "127.0.0.1:8000").await?;
+    let adapter = DefaultAPIAdapter::new(
+        Arc::new(relation_db),
+        Arc::new(smt_manager),
+        Arc::new(MockCkbRpc::default()),
+    );
+    let _ = run_server(
+        Arc::new(adapter),
+        "127.0.0.1:8000",
+        ServerOptions::default(),
+    )
+    .await?;
 
     Ok(())
 }
+
+#[test]
+fn test_page_offset() {
+    assert_eq!(page_offset(1, 10).unwrap(), 0);
+    assert_eq!(page_offset(3, 10).unwrap(), 20);
+
+    assert!(page_offset(0, 10).is_err());
+    assert!(page_offset(1, 0).is_err());
+    assert!(page_offset(u64::MAX, 2).is_err());
+    assert!(page_offset(2, u64::MAX / 2).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_bounded_join() {
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let square = |n: u64| {
+        let in_flight = Arc::clone(&in_flight);
+        let max_in_flight = Arc::clone(&max_in_flight);
+        async move {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(now, Ordering::SeqCst);
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(n * n)
+        }
+    };
+
+    let items = (0..50).collect::<Vec<u64>>();
+    let res = bounded_join(items.clone(), 3, square).await.unwrap();
+    assert_eq!(res, items.iter().map(|n| n * n).collect::<Vec<_>>());
+    assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+
+    let err = bounded_join(items, 3, |n: u64| async move {
+        if n == 7 {
+            Err(AnyError::msg("failed"))
+        } else {
+            Ok(n)
+        }
+    })
+    .await;
+    assert!(err.is_err());
+}
+
+#[tokio::test]
+async fn test_get_network_type() {
+    let (_, _, adapter) = adapter("network", MockCkbRpc::default()).await;
+    let module = AxonStatusRpc::new(Arc::new(adapter)).into_rpc();
+
+    let _guard = NETWORK_TYPE_LOCK.lock().await;
+    for network_type in [NetworkType::Mainnet, NetworkType::Testnet] {
+        set_network_type(network_type.clone());
+        let res: NetworkType = module.call("getNetworkType", rpc_params![]).await.unwrap();
+        assert_eq!(res, network_type);
+    }
+}
+
+#[tokio::test]
+async fn test_system_health() {
+    let (_, _, adapter) = adapter("health", MockCkbRpc::default()).await;
+    let module = AxonStatusRpc::new(Arc::new(adapter)).into_rpc();
+
+    module
+        .call::<_, ()>("systemHealth", rpc_params![])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_script_config() {
+    let (_, _, adapter) = adapter("script_config", MockCkbRpc::default()).await;
+    let module = AxonStatusRpc::new(Arc::new(adapter)).into_rpc();
+
+    let configs: Vec<ScriptConfig> = module.call("getScriptConfig", rpc_params![]).await.unwrap();
+    let kinds = configs.iter().map(|c| c.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        ScriptKind::StakeLock,
+        ScriptKind::StakeSmt,
+        ScriptKind::DelegateRequirement,
+        ScriptKind::DelegateLock,
+        ScriptKind::DelegateSmt,
+        ScriptKind::WithdrawLock,
+        ScriptKind::Metadata,
+        ScriptKind::Checkpoint,
+    ]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_account_summary_consistency() {
+    let (mut writer, module) = module("summary").await;
+    let addr = H160::zero();
+
+    // Each record adds 10 and carries the number of records so far as its rate,
+    // so the rate and the total of a summary agree only if they are read
+    // together.
+    let writes = tokio::spawn(async move {
+        for i in 1..=50u32 {
+            let mut data = mock_data(format!("0x{:02x}", i), 10).await.unwrap();
+            data.stake_rate = Set(i.to_string());
+            writer.insert(data).await.unwrap();
+        }
+        writer
+    });
+    while !writes.is_finished() {
+        let summary: AccountSummary = module
+            .call("getAccountSummary", rpc_params![addr])
+            .await
+            .unwrap();
+        if let Some(rate) = summary.stake_rate {
+            assert_eq!(
+                rate.stake_rate,
+                (summary.stake_state.total_amount / 10).to_string()
+            );
+        }
+    }
+    let mut writer = writes.await.unwrap();
+
+    // Once the writes are over, the summary is the rate and the state.
+    let summary: AccountSummary = module
+        .call("getAccountSummary", rpc_params![addr])
+        .await
+        .unwrap();
+    let rate: StakeRate = module
+        .call("getStakeRate", rpc_params![addr])
+        .await
+        .unwrap();
+    let state: StakeState = module
+        .call("getStakeState", rpc_params![addr])
+        .await
+        .unwrap();
+    assert_eq!(summary.stake_rate, Some(rate));
+    assert_eq!(summary.stake_state, state);
+    assert_eq!(state.total_amount, 500);
+
+    let mut data = mock_data("0xff".to_owned(), 50).await.unwrap();
+    data.stake_amount = Set(3);
+    writer.insert(data).await.unwrap();
+    let summary: AccountSummary = module
+        .call("getAccountSummary", rpc_params![addr])
+        .await
+        .unwrap();
+    let rate = summary.stake_rate.unwrap();
+    assert_eq!(rate.stake_rate_bps, 7500);
+    assert_eq!(
+        (rate.stake_ratio.as_str(), rate.delegate_ratio.as_str()),
+        ("0.7500", "0.2500")
+    );
+}
+
+#[tokio::test]
+async fn test_stake_amount_sources() {
+    let (mut writer, smt_manager, adapter) = adapter("amount_sources", MockCkbRpc::default()).await;
+    let module = StatusRpcModule::new(Arc::new(adapter)).into_rpc();
+
+    // 100 was staked before, 10 more is staked in the first epoch by two
+    // transactions, and 10 more in the next one.
+    for (hash, epoch, amount) in [("0x01", 1, 4), ("0x02", 1, 6), ("0x03", 2, 10)] {
+        let mut data = mock_data(hash.to_owned(), amount).await.unwrap();
+        data.operation = Set(OperationType::Stake as u32);
+        data.epoch = Set(epoch);
+        writer.insert(data).await.unwrap();
+    }
+    for (epoch, amount) in [(1, 110), (2, 120)] {
+        StakeSmtStorage::insert(smt_manager.as_ref(), epoch, vec![UserAmount {
+            user: H160::zero(),
+            amount,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+    }
+
+    // One entry per epoch, however many transactions it has.
+    let smt: Vec<StakeAmount> = module
+        .call("getStakeAmountByEpoch", rpc_params![
+            OperationType::Stake,
+            1,
+            10
+        ])
+        .await
+        .unwrap();
+    let smt = smt
+        .into_iter()
+        .map(|a| (a.epoch, a.amount))
+        .collect::<Vec<_>>();
+    assert_eq!(smt, vec![(1, "110".to_owned()), (2, "120".to_owned())]);
+
+    // The SMT has no rewards.
+    let res: Result<Vec<StakeAmount>, _> = module
+        .call("getStakeAmountByEpoch", rpc_params![
+            OperationType::Reward,
+            1,
+            10
+        ])
+        .await;
+    assert!(res.is_err());
+
+    let history: Vec<StakeAmount> = module
+        .call("getStakeAmountByEpoch", rpc_params![
+            OperationType::Stake,
+            1,
+            10,
+            AmountSource::History
+        ])
+        .await
+        .unwrap();
+    let history = history
+        .iter()
+        .map(|a| a.amount.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(history, vec!["4", "6", "10"]);
+}
+
+#[tokio::test]
+async fn test_reward_states_alignment() {
+    let (mut writer, module) = module("reward_states").await;
+    let delegator = H160::from_low_u64_be(1);
+    let staker = H160::from_low_u64_be(2);
+    let absent = H160::from_low_u64_be(3);
+
+    for hash in ["0x01", "0x02"] {
+        let mut data = mock_data(hash.to_owned(), 10).await.unwrap();
+        data.address = Set(to_hex_address(&delegator));
+        writer.insert(data).await.unwrap();
+    }
+    let mut data = mock_data("0x03".to_owned(), 10).await.unwrap();
+    data.address = Set(to_hex_address(&staker));
+    data.operation = Set(OperationType::Stake as u32);
+    data.epoch = Set(3);
+    writer.insert(data).await.unwrap();
+
+    let states: Vec<RewardState> = module
+        .call("getRewardStates", rpc_params![vec![
+            absent, staker, delegator, absent
+        ]])
+        .await
+        .unwrap();
+    assert_eq!(states, vec![
+        RewardState::default(),
+        RewardState {
+            lock_amount:   3,
+            unlock_amount: 0,
+            exists:        true,
+        },
+        RewardState {
+            lock_amount:   0,
+            unlock_amount: 2,
+            exists:        true,
+        },
+        RewardState::default(),
+    ]);
+
+    for (addr, state) in [absent, staker, delegator].into_iter().zip(&states) {
+        let single: RewardState = module
+            .call("getRewardState", rpc_params![addr])
+            .await
+            .unwrap();
+        assert_eq!(&single, state);
+    }
+
+    let res = module
+        .call::<_, Vec<RewardState>>("getRewardStates", rpc_params![vec![absent; 101]])
+        .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_address_state_exists() {
+    let (mut writer, module) = module("address_state_exists").await;
+    let unknown = H160::from_low_u64_be(1);
+    let zeroed = H160::from_low_u64_be(2);
+
+    let mut data = mock_data("0x01".to_owned(), 0).await.unwrap();
+    data.address = Set(to_hex_address(&zeroed));
+    data.operation = Set(OperationType::Stake as u32);
+    data.epoch = Set(0);
+    data.delegate_amount = Set(0);
+    data.withdrawable_amount = Set(0);
+    writer.insert(data).await.unwrap();
+
+    for (addr, exists) in [(unknown, false), (zeroed, true)] {
+        let stake: StakeState = module
+            .call("getStakeState", rpc_params![addr])
+            .await
+            .unwrap();
+        assert_eq!(stake, StakeState {
+            total_amount: 0,
+            stake_amount: 0,
+            delegate_amount: 0,
+            withdrawable_amount: 0,
+            exists,
+        });
+
+        let reward: RewardState = module
+            .call("getRewardState", rpc_params![addr])
+            .await
+            .unwrap();
+        assert_eq!(reward, RewardState {
+            lock_amount: 0,
+            unlock_amount: 0,
+            exists,
+        });
+    }
+}
+
+#[tokio::test]
+async fn test_top_stake_address_ckb_format() {
+    let (mut writer, module) = module("top_stake_address_ckb_format").await;
+    let staker = H160::from_low_u64_be(1);
+
+    let mut data = mock_data("0x01".to_owned(), 10).await.unwrap();
+    data.address = Set(to_hex_address(&staker));
+    data.operation = Set(OperationType::Stake as u32);
+    writer.insert(data).await.unwrap();
+
+    let _guard = NETWORK_TYPE_LOCK.lock().await;
+    set_network_type(NetworkType::Testnet);
+    let top: Vec<AddressAmount> = module
+        .call("getTopStakeAddress", rpc_params![1, 10, AddressFormat::Ckb])
+        .await
+        .unwrap();
+    assert_eq!(
+        top.into_iter()
+            .map(|a| (a.address, a.amount))
+            .collect::<Vec<_>>(),
+        vec![(
+            "ckt1qrejnmlar3r452tcg57gvq8patctcgy8acync0hxfnyka35ywafvkqgpqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqpqqkypl48"
+                .to_owned(),
+            "10".to_owned()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn test_stake_history_epochs() {
+    let (mut writer, module) = module("stake_history_epochs").await;
+    let addr = H160::zero();
+
+    let mut data = mock_data("0x01".to_owned(), 100).await.unwrap();
+    data.operation = Set(OperationType::Stake as u32);
+    data.event = Set(HistoryEvent::Add as u32);
+    data.epoch = Set(3);
+    data.inauguration_epoch = Set(Some(3 + INAUGURATION as u32));
+    writer.insert(data).await.unwrap();
+
+    // A record whose cell is not known takes effect from an unknown epoch.
+    let mut data = mock_data("0x02".to_owned(), 100).await.unwrap();
+    data.operation = Set(OperationType::Stake as u32);
+    data.event = Set(HistoryEvent::Add as u32);
+    data.epoch = Set(4);
+    writer.insert(data).await.unwrap();
+
+    let history: Vec<StakeHistory> = module
+        .call("getStakeHistory", rpc_params![
+            addr,
+            1,
+            10,
+            HistoryEvent::Add,
+            OperationType::Stake
+        ])
+        .await
+        .unwrap();
+    let epochs = history
+        .iter()
+        .map(|h| (h.epoch, h.inauguration_epoch))
+        .collect::<Vec<_>>();
+    assert_eq!(epochs, vec![(3, Some(3 + INAUGURATION as u32)), (4, None)]);
+}
+
+#[tokio::test]
+async fn test_reward_history_paging() {
+    let (mut writer, module) = module("reward_history_paging").await;
+    let addr = H160::zero();
+
+    for epoch in 1..=3 {
+        let mut data = mock_data(format!("0x0{}", epoch), 10).await.unwrap();
+        data.operation = Set(OperationType::Reward as u32);
+        data.epoch = Set(epoch);
+        writer.insert(data).await.unwrap();
+    }
+
+    let desc: Vec<RewardHistory> = module
+        .call("getRewardHistory", rpc_params![
+            addr,
+            1,
+            2,
+            None::<Cursor>,
+            SortOrder::Desc
+        ])
+        .await
+        .unwrap();
+    assert_eq!(desc.iter().map(|r| r.epoch).collect::<Vec<_>>(), vec![3, 2]);
+
+    let rest: Vec<RewardHistory> = module
+        .call("getRewardHistory", rpc_params![
+            addr,
+            1,
+            2,
+            desc[1].cursor.clone(),
+            SortOrder::Desc
+        ])
+        .await
+        .unwrap();
+    assert_eq!(rest.iter().map(|r| r.epoch).collect::<Vec<_>>(), vec![1]);
+
+    let asc: Vec<RewardHistory> = module
+        .call("getRewardHistory", rpc_params![addr, 2, 2])
+        .await
+        .unwrap();
+    assert_eq!(asc.iter().map(|r| r.epoch).collect::<Vec<_>>(), vec![3]);
+}
+
+#[tokio::test]
+async fn test_stake_transaction_detail() {
+    let item = StakeItem {
+        is_increase:        true,
+        amount:             100,
+        inauguration_epoch: 3,
+    };
+    let stake_data = StakeAtCellData::new_builder()
+        .lock(
+            StakeAtCellLockData::new_builder()
+                .delta(item.clone().into())
+                .build(),
+        )
+        .build();
+    let data = token_cell_data(item.amount, stake_data.as_bytes());
+    let _guard = NETWORK_TYPE_LOCK.lock().await;
+    set_network_type(NetworkType::Testnet);
+    let lock = Script::new_builder()
+        .code_hash(STAKE_LOCK_TESTNET.code_hash.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .build();
+    let tx = TransactionBuilder::default()
+        .output(CellOutput::new_builder().lock(lock).build())
+        .output_data(data.pack())
+        .build();
+    let tx_hash = H256::from_slice(tx.hash().as_slice());
+    let ckb_rpc = MockCkbRpc {
+        txs: HashMap::from([(to_ckb_h256(&tx_hash), tx)]),
+        ..Default::default()
+    };
+
+    let (mut writer, _, adapter) = adapter("stake_transaction", ckb_rpc).await;
+    let module = StatusRpcModule::new(Arc::new(adapter)).into_rpc();
+
+    let mut data = mock_data(format!("{:#x}", tx_hash), 100).await.unwrap();
+    data.operation = Set(OperationType::Stake as u32);
+    data.event = Set(0);
+    writer.insert(data).await.unwrap();
+
+    let detail: Option<StakeTransactionDetail> = module
+        .call("getStakeTransaction", rpc_params![tx_hash])
+        .await
+        .unwrap();
+    let detail = detail.unwrap();
+    assert_eq!(detail.stake_delta, Some(StakeDelta::from(item)));
+    assert_eq!(
+        detail.stake_delta.unwrap().amount,
+        detail.record.amount.to_string()
+    );
+    assert_eq!(detail.record.transaction.hash, to_ckb_h256(&tx_hash));
+
+    let missing: Option<StakeTransactionDetail> = module
+        .call("getStakeTransaction", rpc_params![H256::zero()])
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+}
+
+#[tokio::test]
+async fn test_smt_roots() {
+    let (_, smt_manager, adapter) = adapter("smt_roots", MockCkbRpc::default()).await;
+    let module = StatusRpcModule::new(Arc::new(adapter)).into_rpc();
+
+    let staker = H160::repeat_byte(1);
+    for epoch in [1, 2, 4] {
+        StakeSmtStorage::insert(smt_manager.as_ref(), epoch, vec![UserAmount {
+            user:        staker,
+            amount:      epoch as u128 * 10,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+    }
+
+    let roots: Vec<EpochRoot> = module.call("getSmtRoots", rpc_params![1, 5]).await.unwrap();
+    assert_eq!(roots.iter().map(|r| r.epoch).collect::<Vec<_>>(), vec![
+        1, 2, 4
+    ]);
+    for root in roots {
+        let sub_root = StakeSmtStorage::get_sub_root(smt_manager.as_ref(), root.epoch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(root.root.as_bytes(), sub_root.as_slice());
+    }
+
+    let res = module
+        .call::<_, Vec<EpochRoot>>("getSmtRoots", rpc_params![5, 1])
+        .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_smt_membership() {
+    let (_, smt_manager, adapter) = adapter("smt_membership", MockCkbRpc::default()).await;
+    let module = StatusRpcModule::new(Arc::new(adapter)).into_rpc();
+
+    let staker = H160::repeat_byte(1);
+    let delegator = H160::repeat_byte(2);
+    StakeSmtStorage::insert(smt_manager.as_ref(), 1, vec![UserAmount {
+        user:        staker,
+        amount:      100,
+        is_increase: true,
+    }])
+    .await
+    .unwrap();
+    DelegateSmtStorage::insert(smt_manager.as_ref(), 1, staker, vec![UserAmount {
+        user:        delegator,
+        amount:      10,
+        is_increase: true,
+    }])
+    .await
+    .unwrap();
+
+    let is_staker: bool = module
+        .call("isStaker", rpc_params![staker, 1])
+        .await
+        .unwrap();
+    assert!(is_staker);
+    let is_staker: bool = module
+        .call("isStaker", rpc_params![delegator, 1])
+        .await
+        .unwrap();
+    assert!(!is_staker);
+
+    let is_delegator: bool = module
+        .call("isDelegator", rpc_params![staker, delegator, 1])
+        .await
+        .unwrap();
+    assert!(is_delegator);
+    let is_delegator: bool = module
+        .call("isDelegator", rpc_params![staker, delegator, 2])
+        .await
+        .unwrap();
+    assert!(!is_delegator);
+}
+
+#[tokio::test]
+async fn test_validator_set() {
+    let metadata = MetadataCellData::new_builder()
+        .metadata(
+            MetadataList::new_builder()
+                .push(Metadata::new_builder().quorum(to_uint16(1)).build())
+                .build(),
+        )
+        .build();
+    // The metadata cell is found by its type, which depends on the network.
+    let _guard = NETWORK_TYPE_LOCK.lock().await;
+    set_network_type(NetworkType::Testnet);
+    let mut ckb_rpc = MockCkbRpc::default();
+    ckb_rpc.push(
+        CellOutput::new_builder()
+            .type_(
+                Some(tx_builder::ckb::helper::Metadata::type_(&to_ckb_h256(
+                    &H256::zero(),
+                )))
+                .pack(),
+            )
+            .build(),
+        metadata.as_bytes(),
+    );
+
+    let (_, smt_manager, adapter) = adapter("validator_set", ckb_rpc).await;
+    let adapter = adapter.with_metadata_type_id(H256::zero());
+    let module = StatusRpcModule::new(Arc::new(adapter)).into_rpc();
+
+    // Five stakers for the three seats of a quorum of one.
+    let amounts = [30, 50, 10, 40, 20];
+    let stakers = amounts
+        .iter()
+        .enumerate()
+        .map(|(i, amount)| UserAmount {
+            user:        H160::from_low_u64_be(i as u64 + 1),
+            amount:      *amount,
+            is_increase: true,
+        })
+        .collect();
+    StakeSmtStorage::insert(smt_manager.as_ref(), 1, stakers)
+        .await
+        .unwrap();
+
+    let set: Vec<AddressAmount> = module
+        .call("getValidatorSet", rpc_params![1])
+        .await
+        .unwrap();
+    assert_eq!(
+        set.into_iter()
+            .map(|a| (a.address, a.amount))
+            .collect::<Vec<_>>(),
+        vec![
+            (to_hex_address(&H160::from_low_u64_be(2)), "50".to_owned()),
+            (to_hex_address(&H160::from_low_u64_be(4)), "40".to_owned()),
+            (to_hex_address(&H160::from_low_u64_be(1)), "30".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_epoch_stake_amounts() {
+    let (_, smt_manager, adapter) = adapter("epoch_stake_amounts", MockCkbRpc::default()).await;
+    let adapter = Arc::new(adapter);
+    let module = StatusRpcModule::new(Arc::clone(&adapter)).into_rpc();
+
+    for epoch in (1..=20).step_by(2) {
+        StakeSmtStorage::insert(smt_manager.as_ref(), epoch, vec![UserAmount {
+            user:        H160::zero(),
+            amount:      epoch as u128 * 10,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+    }
+
+    let amounts: Vec<StakeAmount> = module
+        .call("getEpochStakeAmounts", rpc_params![
+            OperationType::Stake,
+            1,
+            20
+        ])
+        .await
+        .unwrap();
+    let mut serial = vec![];
+    for epoch in 1..=20u32 {
+        let amount = adapter
+            .get_smt_amount_by_epoch(OperationType::Stake as u32, epoch as u64)
+            .await
+            .unwrap()
+            .unwrap_or_default();
+        serial.push((epoch, amount.to_string()));
+    }
+    assert_eq!(
+        amounts
+            .into_iter()
+            .map(|a| (a.epoch, a.amount))
+            .collect::<Vec<_>>(),
+        serial
+    );
+
+    let res: Result<Vec<StakeAmount>, _> = module
+        .call("getEpochStakeAmounts", rpc_params![
+            OperationType::Stake,
+            5,
+            4
+        ])
+        .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_server_concurrent_connections() {
+    let (_, _, adapter) = adapter("server_connections", MockCkbRpc::default()).await;
+    let options = ServerOptions {
+        backlog: 64,
+        tcp_keepalive: true,
+        max_connections: 32,
+        ..Default::default()
+    };
+    let (handle, addr) = run_server(Arc::new(adapter), "127.0.0.1:0", options)
+        .await
+        .unwrap();
+
+    // Each client holds its own connection, all of them are served.
+    let tasks = (0..32)
+        .map(|_| {
+            tokio::spawn(async move {
+                let client = HttpClientBuilder::default()
+                    .build(format!("http://{}", addr))
+                    .unwrap();
+                client
+                    .request::<NetworkType, _>("getNetworkType", rpc_params![])
+                    .await
+            })
+        })
+        .collect::<Vec<_>>();
+    for task in tasks {
+        assert!(task.await.unwrap().is_ok());
+    }
+
+    handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn test_server_tls() {
+    let (_, _, adapter) = adapter("server_tls", MockCkbRpc::default()).await;
+    let options = ServerOptions {
+        tls: Some(TlsOptions {
+            cert_path: PathBuf::from(TLS_CERT_PATH),
+            key_path:  PathBuf::from(TLS_KEY_PATH),
+        }),
+        ..Default::default()
+    };
+    let (handle, addr) = run_server(Arc::new(adapter), "127.0.0.1:0", options)
+        .await
+        .unwrap();
+
+    let mut roots = RootCertStore::empty();
+    let pem = fs::read(TLS_CERT_PATH).unwrap();
+    for cert in rustls_pemfile::certs(&mut pem.as_slice()).unwrap() {
+        roots.add(&Certificate(cert)).unwrap();
+    }
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let mut stream = TlsConnector::from(Arc::new(config))
+        .connect(ServerName::try_from("localhost").unwrap(), stream)
+        .await
+        .unwrap();
+
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"getNetworkType","params":[]}"#;
+    let request = format!(
+        "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: \
+         {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains(r#""result""#));
+
+    handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn test_server_tls_max_connections() {
+    let (_, _, adapter) = adapter("server_tls_max_connections", MockCkbRpc::default()).await;
+    let options = ServerOptions {
+        max_connections: 1,
+        tls: Some(TlsOptions {
+            cert_path: PathBuf::from(TLS_CERT_PATH),
+            key_path:  PathBuf::from(TLS_KEY_PATH),
+        }),
+        ..Default::default()
+    };
+    let (handle, addr) = run_server(Arc::new(adapter), "127.0.0.1:0", options)
+        .await
+        .unwrap();
+
+    // The first connection holds the only slot while its handshake is pending,
+    // so the server closes the second one right away.
+    let _first = TcpStream::connect(addr).await.unwrap();
+    let mut second = TcpStream::connect(addr).await.unwrap();
+    let mut buf = [0u8; 1];
+    assert!(matches!(second.read(&mut buf).await, Ok(0) | Err(_)));
+
+    handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn test_staker_delegators() {
+    let (mut writer, module) = module("staker_delegators").await;
+    let staker = H160::from_low_u64_be(1);
+    let delegator_a = H160::from_low_u64_be(2);
+    let delegator_b = H160::from_low_u64_be(3);
+
+    let records = [
+        (delegator_a, HistoryEvent::Add, 10),
+        (delegator_a, HistoryEvent::Add, 20),
+        (delegator_a, HistoryEvent::Redeem, 5),
+        (delegator_b, HistoryEvent::Add, 10),
+        (delegator_b, HistoryEvent::Redeem, 10),
+    ];
+    for (i, (delegator, event, amount)) in records.into_iter().enumerate() {
+        let mut data = mock_data(format!("0x{:02x}", i), amount).await.unwrap();
+        data.address = Set(to_hex_address(&delegator));
+        data.event = Set(event as u32);
+        data.staker = Set(to_hex_address(&staker));
+        writer.insert(data).await.unwrap();
+    }
+
+    let delegators: Vec<AddressAmount> = module
+        .call("getStakerDelegators", rpc_params![staker])
+        .await
+        .unwrap();
+    let delegators = delegators
+        .into_iter()
+        .map(|d| (d.address, d.amount))
+        .collect::<Vec<_>>();
+    assert_eq!(delegators, vec![(
+        to_hex_address(&delegator_a),
+        "25".to_string()
+    )]);
+}
@@ -1,13 +1,25 @@
+use std::collections::HashMap;
+
 use crate::Result;
 use async_trait::async_trait;
+use ckb_types::core::TransactionView;
 
-use crate::types::{relation_db::transaction::Model, smt::Address};
+use crate::types::{
+    api::SortOrder,
+    relation_db::transaction::Model,
+    smt::{Address, Amount, Epoch, Root},
+    H256,
+};
 
 #[async_trait]
 pub trait APIAdapter: Send + Sync {
+    /// Check that the storage behind the adapter is reachable.
+    async fn ping(&self) -> Result<()>;
+
     async fn get_records_by_address(
         &self,
         addr: Address,
+        order: SortOrder,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>>;
@@ -16,10 +28,20 @@ pub trait APIAdapter: Send + Sync {
         &self,
         addr: Address,
         operation: u32,
+        order: SortOrder,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>>;
 
+    async fn get_operation_history_after(
+        &self,
+        addr: Address,
+        operation: u32,
+        order: SortOrder,
+        after: Option<u32>,
+        limit: u64,
+    ) -> Result<Vec<Model>>;
+
     async fn get_stake_amount_by_epoch(
         &self,
         operation: u32,
@@ -27,10 +49,61 @@ pub trait APIAdapter: Send + Sync {
         limit: u64,
     ) -> Result<Vec<Model>>;
 
-    async fn get_top_stake_address(&self, operation: u32) -> Result<Vec<Model>>;
+    /// The total amount of the operation in the SMT at the epoch, `None` if
+    /// the SMT has no leaves for the epoch.
+    async fn get_smt_amount_by_epoch(&self, operation: u32, epoch: Epoch)
+        -> Result<Option<Amount>>;
+
+    async fn get_history_by_epoch(
+        &self,
+        epoch: u32,
+        operation: Option<u32>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>>;
+
+    async fn get_top_stake_address(
+        &self,
+        operation: u32,
+        exclude: Vec<Address>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>>;
 
     async fn get_address_state(&self, addr: Address) -> Result<Vec<Model>>;
 
+    async fn get_delegators_of_staker(&self, staker: Address) -> Result<Vec<Model>>;
+
+    /// The most delegators the staker accepts, read from its delegate
+    /// requirement cell.
+    async fn get_maximum_delegators(&self, staker: Address) -> Result<u32>;
+
+    /// The quorum of the current epoch, read from the metadata cell.
+    async fn get_quorum(&self) -> Result<u16>;
+
+    /// The roots of the stake SMT of the epochs in `start..=end`.
+    async fn get_smt_roots(&self, start: Epoch, end: Epoch) -> Result<Vec<(Epoch, Root)>>;
+
+    /// The stakers in the stake SMT of the epoch with their amounts.
+    async fn get_stakers(&self, epoch: Epoch) -> Result<HashMap<Address, Amount>>;
+
+    /// Whether the address is in the stake SMT of the epoch.
+    async fn is_staker(&self, epoch: Epoch, addr: Address) -> Result<bool>;
+
+    /// Whether the delegator is in the delegate SMT of the staker.
+    async fn is_delegator(&self, epoch: Epoch, staker: Address, delegator: Address)
+        -> Result<bool>;
+
+    /// The number of delegators in the delegate SMT of the staker.
+    async fn get_delegator_count(&self, epoch: Epoch, staker: Address) -> Result<u32>;
+
+    async fn get_records_by_addresses(&self, addrs: Vec<Address>) -> Result<Vec<Model>>;
+
+    async fn get_record_by_tx_hash(&self, tx_hash: H256) -> Result<Option<Model>>;
+
+    /// The transaction on CKB, `None` if the node doesn't know it.
+    async fn get_ckb_transaction(&self, tx_hash: H256) -> Result<Option<TransactionView>>;
+
     async fn get_latest_stake_transactions(
         &self,
         offset: u64,
@@ -2,17 +2,30 @@ use crate::Result;
 use async_trait::async_trait;
 
 use crate::types::{
+    api::SortOrder,
     relation_db::transaction::{self, Model},
     smt::Address,
+    H256,
 };
 
 #[async_trait]
 pub trait TransactionStorage {
+    /// Insert the record, or do nothing if the cell of the record, its
+    /// `tx_hash` and `output_index`, is already recorded.
     async fn insert(&mut self, tx_record: transaction::ActiveModel) -> Result<()>;
 
+    /// Check that the database answers a trivial query, failing instead of
+    /// waiting forever if it doesn't.
+    async fn ping(&self) -> Result<()>;
+
+    /// Insert the records in one transaction, either all of them or none. The
+    /// records of already recorded cells are skipped.
+    async fn insert_batch(&mut self, tx_records: Vec<transaction::ActiveModel>) -> Result<()>;
+
     async fn get_records_by_address(
         &self,
         addr: Address,
+        order: SortOrder,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>>;
@@ -21,6 +34,30 @@ pub trait TransactionStorage {
         &self,
         addr: Address,
         operation: u32,
+        order: SortOrder,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>>;
+
+    /// Page the operation history by id, returning the records after the given
+    /// id in the order or from the start if there is none.
+    async fn get_operation_history_after(
+        &self,
+        addr: Address,
+        operation: u32,
+        order: SortOrder,
+        after: Option<u32>,
+        limit: u64,
+    ) -> Result<Vec<Model>>;
+
+    /// The operation history of an address in the epochs
+    /// `start_epoch..=end_epoch`.
+    async fn get_records_by_epoch_range(
+        &self,
+        addr: Address,
+        operation: u32,
+        start_epoch: u32,
+        end_epoch: u32,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>>;
@@ -32,9 +69,36 @@ pub trait TransactionStorage {
         limit: u64,
     ) -> Result<Vec<Model>>;
 
-    async fn get_top_stake_address(&self, operation: u32) -> Result<Vec<Model>>;
+    /// The records of all addresses in the epoch, optionally of one operation.
+    async fn get_history_by_epoch(
+        &self,
+        epoch: u32,
+        operation: Option<u32>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>>;
+
+    /// The records of the operation with a non-zero stake amount, skipping the
+    /// excluded addresses, by total amount from the largest.
+    async fn get_top_stake_address(
+        &self,
+        operation: u32,
+        exclude: Vec<Address>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>>;
 
     async fn get_address_state(&self, addr: Address) -> Result<Vec<Model>>;
 
+    /// The delegate records made to the staker, read through the staker
+    /// index.
+    async fn get_delegators_of_staker(&self, staker: Address) -> Result<Vec<Model>>;
+
+    /// The records of all the given addresses, read in one query. The caller
+    /// bounds the addresses, they all go into one `IN` clause.
+    async fn get_records_by_addresses(&self, addrs: Vec<Address>) -> Result<Vec<Model>>;
+
+    async fn get_record_by_tx_hash(&self, tx_hash: H256) -> Result<Option<Model>>;
+
     async fn get_latest_stake_transactions(&self, offset: u64, limit: u64) -> Result<Vec<Model>>;
 }
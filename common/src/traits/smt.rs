@@ -12,20 +12,33 @@ use crate::types::smt::{
 pub trait StakeSmtStorage: Send + Sync {
     async fn new_epoch(&self, epoch: Epoch) -> Result<()>;
 
+    /// Discard the sub SMTs of the epochs after `epoch` and their roots in
+    /// the top SMT, e.g. on a reorg. An error if `epoch` is pruned.
+    async fn rollback_to_epoch(&self, epoch: Epoch) -> Result<()>;
+
     async fn insert(&self, epoch: Epoch, stakers: Vec<UserAmount>) -> Result<()>;
 
     async fn remove(&self, epoch: Epoch, staker: Vec<Staker>) -> Result<()>;
 
     async fn get_amount(&self, epoch: Epoch, staker: Staker) -> Result<Option<Amount>>;
 
+    /// Whether the staker has a leaf in the SMT of the epoch.
+    async fn contains(&self, epoch: Epoch, staker: Staker) -> Result<bool>;
+
     async fn get_sub_leaves(&self, epoch: Epoch) -> Result<HashMap<Staker, Amount>>;
 
+    /// The root of the SMT of the epoch, the zero root if the epoch has no
+    /// leaves. It is always `Some`.
     async fn get_sub_root(&self, epoch: Epoch) -> Result<Option<Root>>;
 
     async fn get_sub_roots(&self, epochs: Vec<Epoch>) -> Result<HashMap<Epoch, Option<Root>>>;
 
     async fn get_top_root(&self) -> Result<Root>;
 
+    /// The roots of the epochs in `start..=end` as stored in the top SMT, in
+    /// order and without the epochs that have none.
+    async fn root_history(&self, start: Epoch, end: Epoch) -> Result<Vec<(Epoch, Root)>>;
+
     async fn generate_sub_proof(&self, epoch: Epoch, stakers: Vec<Staker>) -> Result<Proof>;
 
     async fn generate_top_proof(&self, epochs: Vec<Epoch>) -> Result<Proof>;
@@ -35,9 +48,22 @@ pub trait StakeSmtStorage: Send + Sync {
 pub trait DelegateSmtStorage: Send + Sync {
     async fn new_epoch(&self, epoch: Epoch) -> Result<()>;
 
+    /// Discard the sub SMTs of every staker for the epochs after `epoch` and
+    /// their roots in the top SMTs, e.g. on a reorg. An error if `epoch` is
+    /// pruned.
+    async fn rollback_to_epoch(&self, epoch: Epoch) -> Result<()>;
+
     async fn insert(&self, epoch: Epoch, staker: Staker, delegators: Vec<UserAmount>)
         -> Result<()>;
 
+    /// Replace the delegators of several stakers at once, each sub SMT and
+    /// its top SMT is updated a single time.
+    async fn insert_many(
+        &self,
+        epoch: Epoch,
+        entries: HashMap<Staker, Vec<UserAmount>>,
+    ) -> Result<()>;
+
     async fn remove(&self, epoch: Epoch, delegators: Vec<(Staker, Delegator)>) -> Result<()>;
 
     async fn get_amount(
@@ -47,12 +73,26 @@ pub trait DelegateSmtStorage: Send + Sync {
         delegator: Delegator,
     ) -> Result<Option<Amount>>;
 
+    /// The amounts of the delegators in the staker's SMT of the epoch, in the
+    /// order given and `None` for the absent ones, read from one snapshot.
+    async fn get_amounts(
+        &self,
+        epoch: Epoch,
+        staker: Staker,
+        delegators: &[Delegator],
+    ) -> Result<Vec<Option<Amount>>>;
+
+    /// Whether the delegator has a leaf in the staker's SMT of the epoch.
+    async fn contains(&self, epoch: Epoch, staker: Staker, delegator: Delegator) -> Result<bool>;
+
     async fn get_sub_leaves(
         &self,
         epoch: Epoch,
         staker: Staker,
     ) -> Result<HashMap<Delegator, Amount>>;
 
+    /// The root of the staker's SMT of the epoch, the zero root if the staker
+    /// has no delegators in the epoch. It is always `Some`.
     async fn get_sub_root(&self, epoch: Epoch, staker: Staker) -> Result<Option<Root>>;
 
     async fn get_sub_roots(
@@ -63,6 +103,14 @@ pub trait DelegateSmtStorage: Send + Sync {
 
     async fn get_top_root(&self, staker: Staker) -> Result<Root>;
 
+    /// The root of the staker's delegations committed to the top SMT at the
+    /// epoch.
+    async fn get_delegate_root_at_epoch(
+        &self,
+        staker: Staker,
+        epoch: Epoch,
+    ) -> Result<Option<Root>>;
+
     async fn get_top_roots(&self, stakers: Vec<Staker>) -> Result<HashMap<Staker, Root>>;
 
     async fn generate_sub_proof(
@@ -1,8 +1,11 @@
 use crate::types::H160;
+use ckb_jsonrpc_types::{CellDep, ScriptHashType};
 use ckb_types::H256;
 use serde::{Deserialize, Serialize};
 
 use crate::types::axon_rpc_client::{Header, Metadata};
+use crate::types::tx_builder::{ScriptKind, StakeItem};
+use crate::utils::round::{div_round, RoundingMode};
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct ChainState {
@@ -71,6 +74,13 @@ impl From<u32> for OperationStatus {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AddressFormat {
+    #[default]
+    Hex,
+    Ckb,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub enum LockStatusType {
     Lock,
@@ -87,17 +97,78 @@ impl From<u32> for LockStatusType {
     }
 }
 
+/// Where the amount of an epoch is read from. `Smt` is the balance at the
+/// epoch, the sum of the leaves of the SMT. `History` is the flow, the amount
+/// recorded by the transactions of the epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum AmountSource {
+    #[default]
+    Smt,
+    History,
+}
+
+/// The order of a history by id, newest first by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StakeAmount {
     pub epoch:  u32,
     pub amount: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// The root of the stake SMT of an epoch, as committed in the stake top SMT.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EpochRoot {
+    pub epoch: u64,
+    pub root:  H256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct StakeRate {
-    pub address:       String,
-    pub stake_rate:    String,
-    pub delegate_rate: String,
+    pub address:           String,
+    pub stake_rate:        String,
+    pub delegate_rate:     String,
+    pub stake_rate_bps:    u32,
+    pub delegate_rate_bps: u32,
+    /// The shares of the amounts as decimals, rounded by the display settings
+    /// of the server.
+    pub stake_ratio:       String,
+    pub delegate_ratio:    String,
+}
+
+impl StakeRate {
+    pub const TOTAL_BPS: u32 = 10000;
+
+    /// Split the rates in basis points from the integer amounts, so the two
+    /// parts always sum up to `TOTAL_BPS` unless both amounts are zero.
+    pub fn basis_points(stake_amount: u64, delegate_amount: u64) -> (u32, u32) {
+        let total = stake_amount as u128 + delegate_amount as u128;
+        if total == 0 {
+            return (0, 0);
+        }
+
+        let stake_bps = div_round(
+            stake_amount as u128 * Self::TOTAL_BPS as u128,
+            total,
+            RoundingMode::Round,
+        ) as u32;
+        (stake_bps, Self::TOTAL_BPS - stake_bps)
+    }
+}
+
+/// A script used by the transaction builders on the current network.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ScriptConfig {
+    pub kind:      ScriptKind,
+    pub code_hash: H256,
+    pub hash_type: ScriptHashType,
+    /// The cell dep after the configured overrides.
+    pub cell_dep:  CellDep,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -106,21 +177,93 @@ pub struct AddressAmount {
     pub amount:  String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct StakeState {
     pub total_amount:        u32,
     pub stake_amount:        u32,
     pub delegate_amount:     u32,
     pub withdrawable_amount: u32,
+    /// Whether the address has any record, the amounts of an unknown address
+    /// are all zero.
+    #[serde(default)]
+    pub exists:              bool,
+}
+
+/// The stake rate and state of an account, read from the same records.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AccountSummary {
+    pub stake_rate:  Option<StakeRate>,
+    pub stake_state: StakeState,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StakeHistory {
-    pub id:           String,
-    pub amount:       u32,
-    pub event:        HistoryEvent,
-    pub status:       OperationStatus,
-    pub transactions: Vec<HistoryTransactions>,
+    pub id:                 String,
+    pub amount:             u32,
+    /// The epoch the record is made in.
+    pub epoch:              u32,
+    /// The epoch from which the record takes effect, if it is known.
+    pub inauguration_epoch: Option<u32>,
+    pub event:              HistoryEvent,
+    pub status:             OperationStatus,
+    pub transactions:       Vec<HistoryTransactions>,
+    pub cursor:             Cursor,
+}
+
+/// An opaque position in a history. Paging with the cursor of the last seen
+/// item never skips or repeats items, even if new records arrive meanwhile.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Cursor(String);
+
+impl Cursor {
+    pub fn new(id: u32) -> Self {
+        Cursor(faster_hex::hex_string(&id.to_be_bytes()))
+    }
+
+    pub fn id(&self) -> Option<u32> {
+        let mut buf = [0u8; 4];
+        if self.0.len() != buf.len() * 2 {
+            return None;
+        }
+        faster_hex::hex_decode(self.0.as_bytes(), &mut buf).ok()?;
+        Some(u32::from_be_bytes(buf))
+    }
+}
+
+/// A record of any address in an epoch.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EpochHistory {
+    pub address:     String,
+    pub operation:   OperationType,
+    pub event:       HistoryEvent,
+    pub amount:      u32,
+    pub transaction: HistoryTransactions,
+}
+
+/// A stake transaction with the stake delta decoded from its stake AT cell,
+/// which is `None` if the transaction has no such cell.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StakeTransactionDetail {
+    pub record:      EpochHistory,
+    pub stake_delta: Option<StakeDelta>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StakeDelta {
+    pub amount:             String,
+    pub is_increase:        bool,
+    pub inauguration_epoch: u64,
+}
+
+impl From<StakeItem> for StakeDelta {
+    fn from(item: StakeItem) -> Self {
+        StakeDelta {
+            amount:             item.amount.to_string(),
+            is_increase:        item.is_increase,
+            inauguration_epoch: item.inauguration_epoch,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -130,10 +273,14 @@ pub struct HistoryTransactions {
     pub timestamp: u64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct RewardState {
     pub lock_amount:   u32,
     pub unlock_amount: u32,
+    /// Whether the address has any record, the amounts of an unknown address
+    /// are all zero.
+    #[serde(default)]
+    pub exists:        bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -142,6 +289,7 @@ pub struct RewardHistory {
     pub amount: u32,
     pub locked: bool,
     pub from:   RewardFrom,
+    pub cursor: Cursor,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -151,6 +299,24 @@ pub struct RewardFrom {
     pub amount:      u64,
 }
 
+/// How many more delegators a staker accepts in an epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DelegateCapacity {
+    pub max_delegator_size: u32,
+    pub delegator_count:    u32,
+    pub remaining:          u32,
+}
+
+impl DelegateCapacity {
+    pub fn new(max_delegator_size: u32, delegator_count: u32) -> Self {
+        DelegateCapacity {
+            max_delegator_size,
+            delegator_count,
+            remaining: max_delegator_size.saturating_sub(delegator_count),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StakeTransaction {
     pub timestamp: u64,
@@ -158,3 +324,40 @@ pub struct StakeTransaction {
     pub amount:    u64,
     pub status:    OperationStatus,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stake_rate_basis_points() {
+        for (stake, delegate) in [(1, 2), (1, 1), (2, 3), (u64::MAX, 7), (100, 0)] {
+            let (stake_bps, delegate_bps) = StakeRate::basis_points(stake, delegate);
+            assert_eq!(stake_bps + delegate_bps, StakeRate::TOTAL_BPS);
+
+            let rate = stake as f64 / (stake as f64 + delegate as f64);
+            let diff = (rate * StakeRate::TOTAL_BPS as f64 - stake_bps as f64).abs();
+            assert!(diff <= 0.5);
+        }
+
+        assert_eq!(StakeRate::basis_points(0, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_delegate_capacity() {
+        assert_eq!(DelegateCapacity::new(3, 2).remaining, 1);
+        assert_eq!(DelegateCapacity::new(3, 3).remaining, 0);
+        // The limit may be lowered below the current delegators.
+        assert_eq!(DelegateCapacity::new(2, 3).remaining, 0);
+    }
+
+    #[test]
+    fn test_cursor() {
+        for id in [0, 1, 0x1234, u32::MAX] {
+            assert_eq!(Cursor::new(id).id(), Some(id));
+        }
+
+        assert_eq!(Cursor("zz".to_string()).id(), None);
+        assert_eq!(Cursor("0000000g".to_string()).id(), None);
+    }
+}
@@ -21,6 +21,9 @@ pub struct Model {
     pub delegate_rate:       String,
     pub epoch:               u32,
     pub status:              u32,
+    pub staker:              String,
+    pub output_index:        u32,
+    pub inauguration_epoch:  Option<u32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
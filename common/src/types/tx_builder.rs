@@ -15,7 +15,8 @@ use ckb_types::{H160, H256};
 use molecule::prelude::{Builder, Byte, Entity};
 use rlp::Encodable;
 use rlp_derive::{RlpDecodable, RlpEncodable};
-use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
 
 use crate::types::primitive::Hasher;
 use crate::utils::convert::*;
@@ -49,6 +50,18 @@ impl<'a> Deserialize<'a> for NetworkType {
     }
 }
 
+impl Serialize for NetworkType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            NetworkType::Mainnet => serializer.serialize_str("mainnet"),
+            NetworkType::Testnet => serializer.serialize_str("testnet"),
+        }
+    }
+}
+
 struct NetworkTypeVisitor;
 
 impl<'a> Visitor<'a> for NetworkTypeVisitor {
@@ -89,6 +102,33 @@ impl FromStr for NetworkType {
     }
 }
 
+/// The scripts whose cell dep out-points can be overridden, for networks
+/// with redeployed scripts.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptKind {
+    OmniLock,
+    Secp256k1,
+    AlwaysSuccess,
+    Xudt,
+    Selection,
+    Checkpoint,
+    Metadata,
+    StakeLock,
+    StakeSmt,
+    DelegateRequirement,
+    DelegateLock,
+    DelegateSmt,
+    WithdrawLock,
+    RewardSmt,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CellDepOverride {
+    pub tx_hash: H256,
+    pub index:   u32,
+}
+
 pub struct FirstStakeInfo {
     pub l1_pub_key:  Byte65,
     pub bls_pub_key: Byte48,
@@ -153,6 +193,26 @@ impl DelegateItem {
             total_amount: 0,
         }
     }
+
+    /// Rejects the items that can't form a meaningful delegate delta.
+    pub fn validate(&self) -> Result<(), DelegateItemError> {
+        if self.amount == 0 {
+            return Err(DelegateItemError::ZeroAmount);
+        }
+        if self.staker == H160::default() {
+            return Err(DelegateItemError::ZeroStaker);
+        }
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum DelegateItemError {
+    #[error("the amount is zero")]
+    ZeroAmount,
+
+    #[error("the staker is the zero address")]
+    ZeroStaker,
 }
 
 impl From<DelegateItem> for DelegateInfoDelta {
@@ -8,6 +8,30 @@ use axon_types::basic::{
     Byte32, Byte65, Bytes, BytesBuilder, Identity, IdentityOpt, Uint128, Uint16, Uint32, Uint64,
 };
 
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("Data of {len} bytes is too short to split at {at}")]
+pub struct SplitError {
+    pub len: usize,
+    pub at:  usize,
+}
+
+/// Split `bytes` into `[0, at)` and `[at, len)`, unlike `Bytes::split_off` it
+/// fails instead of panicking if there are less than `at` bytes.
+pub fn safe_split(
+    mut bytes: bytes::Bytes,
+    at: usize,
+) -> Result<(bytes::Bytes, bytes::Bytes), SplitError> {
+    if bytes.len() < at {
+        return Err(SplitError {
+            len: bytes.len(),
+            at,
+        });
+    }
+
+    let tail = bytes.split_off(at);
+    Ok((bytes, tail))
+}
+
 pub fn new_u128(v: &[u8]) -> u128 {
     let mut bytes = [0u8; 16];
     bytes.copy_from_slice(&v[0..16]);
@@ -24,6 +48,12 @@ pub fn to_u64(v: &Uint64) -> u64 {
     u64::from_le_bytes(array)
 }
 
+pub fn to_u16(v: &Uint16) -> u16 {
+    let mut array: [u8; 2] = [0u8; 2];
+    array.copy_from_slice(v.as_slice());
+    u16::from_le_bytes(array)
+}
+
 pub fn to_u32(v: &Uint32) -> u32 {
     let mut array: [u8; 4] = [0u8; 4];
     array.copy_from_slice(v.as_slice());
@@ -102,6 +132,12 @@ pub fn to_ckb_h160(v: &ethereum_types::H160) -> H160 {
     H160::from_slice(v.as_bytes()).unwrap()
 }
 
+/// The full `0x` prefixed hex of the address, as the relation database keys
+/// it. The `Display` of `H160` abbreviates the address, so it can't be used.
+pub fn to_hex_address(v: &ethereum_types::H160) -> String {
+    format!("{:#x}", v)
+}
+
 pub fn to_ckb_h256(v: &ethereum_types::H256) -> H256 {
     H256::from_slice(v.as_bytes()).unwrap()
 }
@@ -115,6 +151,22 @@ mod tests {
         assert_eq!(a, to_u128(&to_uint128(a)));
     }
 
+    #[test]
+    fn test_safe_split() {
+        let data = bytes::Bytes::from(vec![1, 2, 3, 4]);
+
+        let (head, tail) = safe_split(data.clone(), 4).unwrap();
+        assert_eq!((head.as_ref(), tail.as_ref()), (&[1, 2, 3, 4][..], &[][..]));
+
+        let (head, tail) = safe_split(data.clone(), 1).unwrap();
+        assert_eq!((head.as_ref(), tail.as_ref()), (&[1][..], &[2, 3, 4][..]));
+
+        assert_eq!(safe_split(data, 5).unwrap_err(), SplitError {
+            len: 4,
+            at:  5,
+        });
+    }
+
     #[test]
     fn test_ckb_byte32() {
         let a = Byte32::default();
@@ -1,3 +1,4 @@
 pub mod codec;
 pub mod convert;
 pub mod hash;
+pub mod round;
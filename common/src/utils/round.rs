@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+/// The most decimals of a displayed value, so that the scaled values fit in
+/// `u128`.
+pub const MAX_PRECISION: u32 = 18;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingMode {
+    Floor,
+    #[default]
+    Round,
+    Ceil,
+}
+
+/// How the decimal values of the responses are rounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AmountDisplay {
+    pub mode:      RoundingMode,
+    /// The number of decimals, capped at [`MAX_PRECISION`].
+    pub precision: u32,
+}
+
+impl Default for AmountDisplay {
+    fn default() -> Self {
+        AmountDisplay {
+            mode:      RoundingMode::Round,
+            precision: 4,
+        }
+    }
+}
+
+impl AmountDisplay {
+    /// Format `numerator / denominator` as a decimal, zero if the denominator
+    /// is zero.
+    pub fn ratio(&self, numerator: u64, denominator: u64) -> String {
+        let precision = self.precision.min(MAX_PRECISION);
+        let scale = 10u128.pow(precision);
+        let value = div_round(numerator as u128 * scale, denominator as u128, self.mode);
+
+        if precision == 0 {
+            value.to_string()
+        } else {
+            format!(
+                "{}.{:0width$}",
+                value / scale,
+                value % scale,
+                width = precision as usize
+            )
+        }
+    }
+}
+
+/// Divide with the given rounding, halves are rounded up by
+/// [`RoundingMode::Round`]. Zero if the denominator is zero.
+pub fn div_round(numerator: u128, denominator: u128, mode: RoundingMode) -> u128 {
+    if denominator == 0 {
+        return 0;
+    }
+
+    let (quotient, remainder) = (numerator / denominator, numerator % denominator);
+    let round_up = match mode {
+        RoundingMode::Floor => false,
+        RoundingMode::Round => remainder >= denominator - remainder,
+        RoundingMode::Ceil => remainder > 0,
+    };
+    quotient + round_up as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div_round() {
+        let cases = [
+            // (numerator, denominator, floor, round, ceil)
+            (0, 3, 0, 0, 0),
+            (6, 3, 2, 2, 2),
+            (7, 3, 2, 2, 3),
+            (8, 3, 2, 3, 3),
+            (5, 2, 2, 3, 3),
+            (u128::MAX, u128::MAX, 1, 1, 1),
+            (
+                u128::MAX,
+                2,
+                u128::MAX / 2,
+                u128::MAX / 2 + 1,
+                u128::MAX / 2 + 1,
+            ),
+            (1, 0, 0, 0, 0),
+        ];
+        for (n, d, floor, round, ceil) in cases {
+            assert_eq!(div_round(n, d, RoundingMode::Floor), floor);
+            assert_eq!(div_round(n, d, RoundingMode::Round), round);
+            assert_eq!(div_round(n, d, RoundingMode::Ceil), ceil);
+        }
+    }
+
+    #[test]
+    fn test_ratio() {
+        let display = |mode, precision| AmountDisplay { mode, precision };
+
+        assert_eq!(display(RoundingMode::Floor, 4).ratio(2, 3), "0.6666");
+        assert_eq!(display(RoundingMode::Round, 4).ratio(2, 3), "0.6667");
+        assert_eq!(display(RoundingMode::Ceil, 4).ratio(1, 3), "0.3334");
+        assert_eq!(display(RoundingMode::Round, 2).ratio(1, 200), "0.01");
+        assert_eq!(display(RoundingMode::Floor, 2).ratio(1, 200), "0.00");
+        assert_eq!(display(RoundingMode::Ceil, 0).ratio(1, 3), "1");
+        assert_eq!(display(RoundingMode::Round, 4).ratio(3, 3), "1.0000");
+        assert_eq!(display(RoundingMode::Round, 4).ratio(0, 0), "0.0000");
+        assert_eq!(
+            display(RoundingMode::Floor, 100).ratio(u64::MAX, u64::MAX),
+            format!("1.{}", "0".repeat(MAX_PRECISION as usize))
+        );
+    }
+}
@@ -1,17 +1,195 @@
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::{fs, io};
 
-use common::types::tx_builder::NetworkType;
+use api::{ServerOptions, TlsOptions};
+use common::types::tx_builder::{CellDepOverride, NetworkType, ScriptKind};
+use common::types::H256;
+use common::utils::round::{AmountDisplay, RoundingMode};
 use serde::{de, Deserialize};
+use storage::relation_db::{ConnectOptions, RetentionPolicy};
+use storage::smt::KvdbOptions;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct SparkConfig {
     pub private_key:        String,
     pub rpc_listen_address: SocketAddr,
+    #[serde(default)]
+    pub rpc_server:         RpcServerConfig,
+    /// The CKB node the stake transactions are fetched from, a local node
+    /// by default.
+    #[serde(default = "default_ckb_node_url")]
+    pub ckb_node_url:       String,
     pub rdb_url:            String,
+    #[serde(default)]
+    pub rdb_pool:           RdbPoolConfig,
+    #[serde(default = "default_auto_migrate")]
+    pub auto_migrate:       bool,
     pub kvdb_path:          PathBuf,
+    #[serde(default)]
+    pub kvdb:               KvdbConfig,
     pub network_type:       NetworkType,
+    /// The type id of the metadata cell, required by
+    /// `getStakerDelegateCapacity`.
+    pub metadata_type_id:   Option<H256>,
+    pub retention:          Option<RetentionConfig>,
+    #[serde(default)]
+    pub cell_deps:          HashMap<ScriptKind, CellDepOverride>,
+}
+
+/// The socket and response settings of the RPC server, the defaults of
+/// [`ServerOptions`] are used for the settings which are not set.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RpcServerConfig {
+    pub backlog:         Option<u32>,
+    pub tcp_keepalive:   Option<bool>,
+    pub max_connections: Option<u32>,
+    pub rounding:        Option<RoundingMode>,
+    pub precision:       Option<u32>,
+    pub tls:             Option<TlsConfig>,
+}
+
+/// The PEM files to serve the RPC over HTTPS, plain HTTP is served if not
+/// set.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path:  PathBuf,
+}
+
+impl From<TlsConfig> for TlsOptions {
+    fn from(config: TlsConfig) -> Self {
+        TlsOptions {
+            cert_path: config.cert_path,
+            key_path:  config.key_path,
+        }
+    }
+}
+
+impl From<RpcServerConfig> for ServerOptions {
+    fn from(config: RpcServerConfig) -> Self {
+        let default = ServerOptions::default();
+        ServerOptions {
+            backlog:         config.backlog.unwrap_or(default.backlog),
+            tcp_keepalive:   config.tcp_keepalive.unwrap_or(default.tcp_keepalive),
+            max_connections: config.max_connections.unwrap_or(default.max_connections),
+            display:         AmountDisplay {
+                mode:      config.rounding.unwrap_or(default.display.mode),
+                precision: config.precision.unwrap_or(default.display.precision),
+            },
+            tls:             config.tls.map(Into::into),
+        }
+    }
+}
+
+/// The connection pool of the relation database, the sqlx defaults are used
+/// for the options which are not set.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RdbPoolConfig {
+    pub max_connections:      Option<u32>,
+    pub min_connections:      Option<u32>,
+    pub connect_timeout_secs: Option<u64>,
+    pub idle_timeout_secs:    Option<u64>,
+    pub sqlx_logging:         Option<bool>,
+}
+
+impl RdbPoolConfig {
+    pub fn connect_options(&self, url: &str) -> ConnectOptions {
+        let mut options = ConnectOptions::new(url.to_owned());
+        if let Some(max) = self.max_connections {
+            options.max_connections(max);
+        }
+        if let Some(min) = self.min_connections {
+            options.min_connections(min);
+        }
+        if let Some(secs) = self.connect_timeout_secs {
+            options.connect_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.idle_timeout_secs {
+            options.idle_timeout(Duration::from_secs(secs));
+        }
+        if let Some(logging) = self.sqlx_logging {
+            options.sqlx_logging(logging);
+        }
+        options
+    }
+}
+
+/// The RocksDB tuning of the SMT database. The options which are not set fall
+/// back to the sync preset if `sync_preset` is on, and to the RocksDB defaults
+/// otherwise.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct KvdbConfig {
+    #[serde(default)]
+    pub sync_preset:         bool,
+    pub write_buffer_size:   Option<usize>,
+    pub max_background_jobs: Option<i32>,
+    pub block_cache_size:    Option<usize>,
+    pub compression:         Option<bool>,
+    /// The epochs before the current one whose sub SMTs are kept, all of
+    /// them if not set.
+    pub keep_epochs:         Option<u64>,
+    #[serde(default = "default_prune_interval")]
+    pub prune_interval_secs: u64,
+}
+
+impl KvdbConfig {
+    /// The epoch to pass to `SmtManager::prune_epochs_before` when
+    /// `current_epoch` is the latest one, none if nothing is to be pruned.
+    pub fn prune_before(&self, current_epoch: u64) -> Option<u64> {
+        let keep = self.keep_epochs?;
+        current_epoch.checked_sub(keep).filter(|epoch| *epoch > 0)
+    }
+}
+
+impl From<KvdbConfig> for KvdbOptions {
+    fn from(config: KvdbConfig) -> Self {
+        let base = if config.sync_preset {
+            KvdbOptions::sync()
+        } else {
+            KvdbOptions::default()
+        };
+        KvdbOptions {
+            write_buffer_size:   config.write_buffer_size.or(base.write_buffer_size),
+            max_background_jobs: config.max_background_jobs.or(base.max_background_jobs),
+            block_cache_size:    config.block_cache_size.or(base.block_cache_size),
+            compression:         config.compression.or(base.compression),
+        }
+    }
+}
+
+/// The retention policy of the transaction history, which is kept forever
+/// if it is not configured. The pruned rows are deleted, their amounts no
+/// longer count in the aggregates served by the RPC.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetentionConfig {
+    pub keep_epochs:         Option<u32>,
+    pub keep_days:           Option<u32>,
+    #[serde(default = "default_prune_interval")]
+    pub prune_interval_secs: u64,
+}
+
+impl From<RetentionConfig> for RetentionPolicy {
+    fn from(config: RetentionConfig) -> Self {
+        RetentionPolicy {
+            keep_epochs: config.keep_epochs,
+            keep_days:   config.keep_days,
+        }
+    }
+}
+
+fn default_ckb_node_url() -> String {
+    "http://127.0.0.1:8114".to_owned()
+}
+
+fn default_auto_migrate() -> bool {
+    true
+}
+
+fn default_prune_interval() -> u64 {
+    60 * 60
 }
 
 /// Parse a config from reader.
@@ -1,24 +1,89 @@
 mod config;
 
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{env, sync::Arc};
 
 use api::{run_server, DefaultAPIAdapter};
-use config::SparkConfig;
-use storage::{SmtManager, TransactionHistory};
-use tx_builder::set_network_type;
+use config::{KvdbConfig, RetentionConfig, SparkConfig};
+use rpc_client::ckb_client::ckb_rpc_client::CkbRpcClient;
+use storage::{relation_db::RetentionPolicy, SmtManager, TransactionHistory};
+use tx_builder::{set_cell_dep_overrides, set_network_type};
 
 #[tokio::main]
 async fn main() {
     let args = env::args().nth(1).expect("Missing env variable");
     let config: SparkConfig = config::parse_file(args).expect("Failed to parse config file");
     set_network_type(config.network_type);
+    set_cell_dep_overrides(config.cell_deps.clone());
 
-    let rdb = Arc::new(TransactionHistory::new(&config.rdb_url).await);
-    let kvdb = Arc::new(SmtManager::new(&config.kvdb_path));
-    let api_adapter = Arc::new(DefaultAPIAdapter::new(rdb, kvdb));
-    let _handle = run_server(api_adapter, config.rpc_listen_address)
-        .await
-        .unwrap();
+    let rdb_options = config.rdb_pool.connect_options(&config.rdb_url);
+    let rdb = if config.auto_migrate {
+        TransactionHistory::new_with_options(rdb_options).await
+    } else {
+        TransactionHistory::new_without_migration(rdb_options)
+            .await
+            .expect("Relation database schema is not up to date")
+    };
+    let rdb = Arc::new(rdb);
+    let kvdb = Arc::new(SmtManager::new_with_options(
+        &config.kvdb_path,
+        &config.kvdb.clone().into(),
+    ));
+    if let Some(retention) = config.retention.clone() {
+        tokio::spawn(run_pruner(Arc::clone(&rdb), retention));
+    }
+    if config.kvdb.keep_epochs.is_some() {
+        tokio::spawn(run_smt_pruner(Arc::clone(&kvdb), config.kvdb.clone()));
+    }
+    let ckb_rpc = Arc::new(CkbRpcClient::new(&config.ckb_node_url));
+    let mut api_adapter = DefaultAPIAdapter::new(rdb, kvdb, ckb_rpc);
+    if let Some(metadata_type_id) = config.metadata_type_id {
+        api_adapter = api_adapter.with_metadata_type_id(metadata_type_id);
+    }
+    let api_adapter = Arc::new(api_adapter);
+    let (_handle, _addr) = run_server(
+        api_adapter,
+        config.rpc_listen_address,
+        config.rpc_server.clone().into(),
+    )
+    .await
+    .unwrap();
 
     println!("Hello, world!");
 }
+
+async fn run_pruner(rdb: Arc<TransactionHistory>, config: RetentionConfig) {
+    let mut interval = tokio::time::interval(Duration::from_secs(config.prune_interval_secs));
+    let policy = RetentionPolicy::from(config);
+
+    loop {
+        interval.tick().await;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or_default();
+        if let Err(e) = rdb.prune(&policy, now).await {
+            log::error!("Failed to prune transaction history: {}", e);
+        }
+    }
+}
+
+async fn run_smt_pruner(kvdb: Arc<SmtManager>, config: KvdbConfig) {
+    let mut interval = tokio::time::interval(Duration::from_secs(config.prune_interval_secs));
+
+    loop {
+        interval.tick().await;
+        let prune_before = match kvdb.latest_epoch() {
+            Ok(latest) => latest.and_then(|epoch| config.prune_before(epoch)),
+            Err(e) => {
+                log::error!("Failed to read the latest SMT epoch: {}", e);
+                continue;
+            }
+        };
+        if let Some(epoch) = prune_before {
+            if let Err(e) = kvdb.prune_epochs_before(epoch) {
+                log::error!("Failed to prune SMT epochs: {}", e);
+            }
+        }
+    }
+}
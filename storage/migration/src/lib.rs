@@ -1,12 +1,20 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20230601_000001_add_staker_column;
+mod m20230615_000001_add_output_index;
+mod m20230620_000001_add_inauguration_epoch;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20230601_000001_add_staker_column::Migration),
+            Box::new(m20230615_000001_add_output_index::Migration),
+            Box::new(m20230620_000001_add_inauguration_epoch::Migration),
+        ]
     }
 }
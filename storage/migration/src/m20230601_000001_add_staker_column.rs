@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+const STAKER_INDEX: &str = "idx-transaction-staker-operation";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The staker a delegate record is made to, empty for the other records.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Transaction::Table)
+                    .add_column(
+                        ColumnDef::new(Transaction::Staker)
+                            .string_len(42)
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name(STAKER_INDEX)
+                    .table(Transaction::Table)
+                    .col(Transaction::Staker)
+                    .col(Transaction::Operation)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(STAKER_INDEX)
+                    .table(Transaction::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Transaction::Table)
+                    .drop_column(Transaction::Staker)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Transaction {
+    Table,
+    Operation,
+    Staker,
+}
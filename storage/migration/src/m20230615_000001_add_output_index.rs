@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+const CELL_INDEX: &str = "idx-transaction-tx-hash-output-index";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The index of the cell in the transaction outputs which a record is
+        // made from, a transaction with several cells has a record per cell.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Transaction::Table)
+                    .add_column(
+                        ColumnDef::new(Transaction::OutputIndex)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The cells of the existing records are unknown, number the records of
+        // each transaction in insertion order so that they stay distinct.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "transaction" SET "output_index" = (
+                    SELECT COUNT(*) FROM "transaction" AS "t"
+                    WHERE "t"."tx_hash" = "transaction"."tx_hash"
+                        AND "t"."id" < "transaction"."id"
+                )"#,
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name(CELL_INDEX)
+                    .table(Transaction::Table)
+                    .col(Transaction::TxHash)
+                    .col(Transaction::OutputIndex)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(CELL_INDEX)
+                    .table(Transaction::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Transaction::Table)
+                    .drop_column(Transaction::OutputIndex)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Transaction {
+    Table,
+    TxHash,
+    OutputIndex,
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::{io, time::Duration};
 
 use migration::DbErr;
 use thiserror::Error;
@@ -10,4 +10,31 @@ pub enum StorageError {
 
     #[error("Sql cursor error {0}")]
     SqlCursorError(DbErr),
+
+    #[error("Sql query error {0}")]
+    SqlQueryError(DbErr),
+
+    #[error("Pending migrations {0:?}")]
+    PendingMigrations(Vec<String>),
+
+    #[error("Sql ping timed out after {0:?}")]
+    PingTimeout(Duration),
+
+    #[error("KVDB error {0}")]
+    Kvdb(#[from] KvdbError),
+}
+
+#[derive(Error, Debug)]
+pub enum KvdbError {
+    #[error("Missing column family {0}")]
+    MissingColumnFamily(String),
+
+    #[error("RocksDB error {0}")]
+    RocksDB(#[from] rocksdb::Error),
+
+    #[error("Epoch {0} is pruned, the SMTs are kept from epoch {1}")]
+    EpochPruned(u64, u64),
+
+    #[error("Invalid {0} value of {1} bytes")]
+    InvalidValue(&'static str, usize),
 }
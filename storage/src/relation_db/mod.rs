@@ -1,61 +1,209 @@
+mod retention;
+
 use crate::error::StorageError;
 use anyhow::Result;
 use async_trait::async_trait;
 use common::traits::query::TransactionStorage;
 use common::types::{
+    api::{OperationType, SortOrder},
     relation_db::transaction::{self, Model},
     smt::Address,
+    H256,
 };
+use common::utils::convert::to_hex_address;
 use migration::{Migrator, MigratorTrait};
-pub use sea_orm::Set;
+pub use retention::RetentionPolicy;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, CursorTrait, Database, DbConn, EntityTrait, QueryFilter,
+    sea_query::OnConflict, ActiveValue, ColumnTrait, ConnectionTrait, CursorTrait, Database,
+    DatabaseConnection, DbConn, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Statement,
+    TransactionTrait,
 };
+pub use sea_orm::{ConnectOptions, Set};
+use std::{collections::BTreeMap, time::Duration};
 
-pub async fn establish_connection(database_url: &str) -> Result<DbConn> {
-    let db = Database::connect(database_url).await?;
+pub async fn establish_connection(options: impl Into<ConnectOptions>) -> Result<DbConn> {
+    let db = Database::connect(options).await?;
     Migrator::up(&db, None).await?;
 
     Ok(db)
 }
 
+/// Connect without migrating, for operators who apply the schema changes
+/// with their own tooling. Fails if the schema is not up to date.
+pub async fn establish_connection_without_migration(
+    options: impl Into<ConnectOptions>,
+) -> Result<DbConn> {
+    let db = Database::connect(options).await?;
+    check_migrations(&db).await?;
+
+    Ok(db)
+}
+
+pub async fn check_migrations(db: &DbConn) -> Result<()> {
+    let pending = Migrator::get_pending_migrations(db)
+        .await?
+        .iter()
+        .map(|m| m.name().to_owned())
+        .collect::<Vec<_>>();
+
+    if pending.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageError::PendingMigrations(pending).into())
+    }
+}
+
+fn order_by_id<Q: QueryOrder>(query: Q, order: SortOrder) -> Q {
+    match order {
+        SortOrder::Asc => query.order_by_asc(transaction::Column::Id),
+        SortOrder::Desc => query.order_by_desc(transaction::Column::Id),
+    }
+}
+
+/// How long a ping waits, e.g. for a connection of an exhausted pool.
+const PING_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The rows of one insert statement, well below the bound variables SQLite
+/// allows per statement.
+const INSERT_BATCH_ROWS: usize = 500;
+
+/// A record is made per cell, so reprocessing a transaction doesn't duplicate
+/// its records.
+fn cell_conflict() -> OnConflict {
+    OnConflict::columns([
+        transaction::Column::TxHash,
+        transaction::Column::OutputIndex,
+    ])
+    .do_nothing()
+    .to_owned()
+}
+
 pub struct TransactionHistory {
     pub db: DbConn,
 }
 
+/// The connections of the pool, for monitoring.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+}
+
 impl TransactionHistory {
     pub async fn new(database_url: &str) -> Self {
-        let db = establish_connection(database_url).await.unwrap();
+        Self::new_with_options(ConnectOptions::new(database_url.to_owned())).await
+    }
+
+    /// Connect with the given pool size, timeouts and logging instead of the
+    /// defaults of sqlx.
+    pub async fn new_with_options(options: ConnectOptions) -> Self {
+        let db = establish_connection(options).await.unwrap();
         Self { db }
     }
+
+    pub async fn new_without_migration(options: impl Into<ConnectOptions>) -> Result<Self> {
+        let db = establish_connection_without_migration(options).await?;
+        Ok(Self { db })
+    }
+
+    /// The stats of the SQLite or Postgres pool, `None` for other
+    /// connections.
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        match &self.db {
+            DatabaseConnection::SqlxSqlitePoolConnection(_) => {
+                let pool = self.db.get_sqlite_connection_pool();
+                Some(PoolStats {
+                    size: pool.size(),
+                    idle: pool.num_idle(),
+                })
+            }
+            DatabaseConnection::SqlxPostgresPoolConnection(_) => {
+                let pool = self.db.get_postgres_connection_pool();
+                Some(PoolStats {
+                    size: pool.size(),
+                    idle: pool.num_idle(),
+                })
+            }
+            _ => None,
+        }
+    }
 }
 
 #[async_trait]
 impl TransactionStorage for TransactionHistory {
     async fn insert(&mut self, tx_record: transaction::ActiveModel) -> Result<()> {
-        let tx_record = tx_record.insert(&self.db).await?;
+        self.insert_batch(vec![tx_record]).await
+    }
+
+    async fn insert_batch(&mut self, tx_records: Vec<transaction::ActiveModel>) -> Result<()> {
+        if tx_records.is_empty() {
+            return Ok(());
+        }
+
+        // The records and total amounts per address, logged once the batch is
+        // committed.
+        let mut per_address: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+        for record in tx_records.iter() {
+            if let (ActiveValue::Set(address), ActiveValue::Set(amount)) =
+                (&record.address, &record.total_amount)
+            {
+                let entry = per_address.entry(address.clone()).or_default();
+                entry.0 += 1;
+                entry.1 += *amount as u64;
+            }
+        }
+
+        let count = tx_records.len();
+        let txn = self.db.begin().await.map_err(StorageError::SqlQueryError)?;
+        let mut inserted = 0;
+        for chunk in tx_records.chunks(INSERT_BATCH_ROWS) {
+            inserted += transaction::Entity::insert_many(chunk.to_vec())
+                .on_conflict(cell_conflict())
+                .exec_without_returning(&txn)
+                .await
+                .map_err(StorageError::SqlQueryError)?;
+        }
+        txn.commit().await.map_err(StorageError::SqlQueryError)?;
+
+        for (address, (records, total_amount)) in per_address.iter() {
+            log::debug!(
+                "{} records with total amount {} for address: {}",
+                records,
+                total_amount,
+                address
+            );
+        }
         log::info!(
-            "Transaction created with address: {}, timestamp: {}, tx_hash: {}",
-            tx_record.address,
-            tx_record.timestamp,
-            tx_record.tx_hash
+            "{} transactions created, {} already recorded",
+            inserted,
+            count as u64 - inserted
         );
         Ok(())
     }
 
+    async fn ping(&self) -> Result<()> {
+        let stmt = Statement::from_string(self.db.get_database_backend(), "SELECT 1".to_owned());
+        match tokio::time::timeout(PING_TIMEOUT, self.db.execute(stmt)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(StorageError::SqlQueryError(e).into()),
+            Err(_) => Err(StorageError::PingTimeout(PING_TIMEOUT).into()),
+        }
+    }
+
     async fn get_records_by_address(
         &self,
         addr: Address,
+        order: SortOrder,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>> {
-        let mut cursor = transaction::Entity::find()
-            .filter(transaction::Column::Address.eq(addr.to_string()))
-            .cursor_by(transaction::Column::Id);
-        cursor.after(offset).before(offset + limit);
-        match cursor.all(&self.db).await {
+        // The offset counts rows, it is not an id, so it can't be used as a cursor.
+        let query = transaction::Entity::find()
+            .filter(transaction::Column::Address.eq(to_hex_address(&addr)));
+        let query = order_by_id(query, order).offset(offset).limit(limit);
+        match query.all(&self.db).await {
             Ok(records) => Ok(records),
-            Err(e) => Err(StorageError::SqlCursorError(e).into()),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
         }
     }
 
@@ -63,17 +211,65 @@ impl TransactionStorage for TransactionHistory {
         &self,
         addr: Address,
         operation: u32,
+        order: SortOrder,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>> {
-        let mut cursor = transaction::Entity::find()
-            .filter(transaction::Column::Address.eq(addr.to_string()))
+        let query = transaction::Entity::find()
+            .filter(transaction::Column::Address.eq(to_hex_address(&addr)))
+            .filter(transaction::Column::Operation.eq(operation));
+        let query = order_by_id(query, order).offset(offset).limit(limit);
+        match query.all(&self.db).await {
+            Ok(records) => Ok(records),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
+        }
+    }
+
+    async fn get_operation_history_after(
+        &self,
+        addr: Address,
+        operation: u32,
+        order: SortOrder,
+        after: Option<u32>,
+        limit: u64,
+    ) -> Result<Vec<Model>> {
+        let mut query = transaction::Entity::find()
+            .filter(transaction::Column::Address.eq(to_hex_address(&addr)))
+            .filter(transaction::Column::Operation.eq(operation));
+        // Past the cursor is above its id in ascending order and below it in
+        // descending order.
+        if let Some(id) = after {
+            query = match order {
+                SortOrder::Asc => query.filter(transaction::Column::Id.gt(id)),
+                SortOrder::Desc => query.filter(transaction::Column::Id.lt(id)),
+            };
+        }
+        let query = order_by_id(query, order).limit(limit);
+        match query.all(&self.db).await {
+            Ok(records) => Ok(records),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
+        }
+    }
+
+    async fn get_records_by_epoch_range(
+        &self,
+        addr: Address,
+        operation: u32,
+        start_epoch: u32,
+        end_epoch: u32,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>> {
+        let query = transaction::Entity::find()
+            .filter(transaction::Column::Address.eq(to_hex_address(&addr)))
             .filter(transaction::Column::Operation.eq(operation))
-            .cursor_by(transaction::Column::Id);
-        cursor.after(offset).before(offset + limit);
-        match cursor.all(&self.db).await {
+            .filter(transaction::Column::Epoch.between(start_epoch, end_epoch))
+            .order_by_asc(transaction::Column::Id)
+            .offset(offset)
+            .limit(limit);
+        match query.all(&self.db).await {
             Ok(records) => Ok(records),
-            Err(e) => Err(StorageError::SqlCursorError(e).into()),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
         }
     }
 
@@ -83,29 +279,66 @@ impl TransactionStorage for TransactionHistory {
         offset: u64,
         limit: u64,
     ) -> Result<Vec<Model>> {
-        let mut cursor = transaction::Entity::find()
+        let query = transaction::Entity::find()
             .filter(transaction::Column::Operation.eq(operation))
-            .cursor_by(transaction::Column::Id);
-        cursor.after(offset).before(offset + limit);
-        match cursor.all(&self.db).await {
+            .order_by_asc(transaction::Column::Id)
+            .offset(offset)
+            .limit(limit);
+        match query.all(&self.db).await {
             Ok(records) => Ok(records),
-            Err(e) => Err(StorageError::SqlCursorError(e).into()),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
         }
     }
 
-    async fn get_top_stake_address(&self, operation: u32) -> Result<Vec<Model>> {
-        let mut cursor = transaction::Entity::find()
+    async fn get_history_by_epoch(
+        &self,
+        epoch: u32,
+        operation: Option<u32>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>> {
+        let mut query = transaction::Entity::find().filter(transaction::Column::Epoch.eq(epoch));
+        if let Some(operation) = operation {
+            query = query.filter(transaction::Column::Operation.eq(operation));
+        }
+        let query = query
+            .order_by_asc(transaction::Column::Id)
+            .offset(offset)
+            .limit(limit);
+        match query.all(&self.db).await {
+            Ok(records) => Ok(records),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
+        }
+    }
+
+    async fn get_top_stake_address(
+        &self,
+        operation: u32,
+        exclude: Vec<Address>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Model>> {
+        let mut query = transaction::Entity::find()
             .filter(transaction::Column::Operation.eq(operation))
-            .cursor_by(transaction::Column::TotalAmount);
-        match cursor.all(&self.db).await {
+            .filter(transaction::Column::StakeAmount.gt(0));
+        if !exclude.is_empty() {
+            query = query
+                .filter(transaction::Column::Address.is_not_in(exclude.iter().map(to_hex_address)));
+        }
+        let query = query
+            .order_by_desc(transaction::Column::TotalAmount)
+            .order_by_asc(transaction::Column::Id)
+            .offset(offset)
+            .limit(limit);
+        match query.all(&self.db).await {
             Ok(records) => Ok(records),
-            Err(e) => Err(StorageError::SqlCursorError(e).into()),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
         }
     }
 
     async fn get_address_state(&self, addr: Address) -> Result<Vec<Model>> {
         let mut cursor = transaction::Entity::find()
-            .filter(transaction::Column::Address.eq(addr.to_string()))
+            .filter(transaction::Column::Address.eq(to_hex_address(&addr)))
             .cursor_by(transaction::Column::Id);
         match cursor.all(&self.db).await {
             Ok(records) => Ok(records),
@@ -113,12 +346,47 @@ impl TransactionStorage for TransactionHistory {
         }
     }
 
+    async fn get_delegators_of_staker(&self, staker: Address) -> Result<Vec<Model>> {
+        let query = transaction::Entity::find()
+            .filter(transaction::Column::Staker.eq(to_hex_address(&staker)))
+            .filter(transaction::Column::Operation.eq(OperationType::Delegate as u32))
+            .order_by_asc(transaction::Column::Id);
+        match query.all(&self.db).await {
+            Ok(records) => Ok(records),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
+        }
+    }
+
+    async fn get_records_by_addresses(&self, addrs: Vec<Address>) -> Result<Vec<Model>> {
+        let query = transaction::Entity::find()
+            .filter(transaction::Column::Address.is_in(addrs.iter().map(to_hex_address)))
+            .order_by_asc(transaction::Column::Id);
+        match query.all(&self.db).await {
+            Ok(records) => Ok(records),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
+        }
+    }
+
+    async fn get_record_by_tx_hash(&self, tx_hash: H256) -> Result<Option<Model>> {
+        let query = transaction::Entity::find()
+            .filter(transaction::Column::TxHash.eq(format!("{:#x}", tx_hash)));
+        match query.one(&self.db).await {
+            Ok(record) => Ok(record),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
+        }
+    }
+
     async fn get_latest_stake_transactions(&self, offset: u64, limit: u64) -> Result<Vec<Model>> {
-        let mut cursor = transaction::Entity::find().cursor_by(transaction::Column::Id);
-        cursor.after(offset).before(offset + limit);
-        match cursor.all(&self.db).await {
+        // The id breaks ties between equal timestamps, so that the order is total and
+        // the pages never overlap.
+        let query = transaction::Entity::find()
+            .order_by_desc(transaction::Column::Timestamp)
+            .order_by_desc(transaction::Column::Id)
+            .offset(offset)
+            .limit(limit);
+        match query.all(&self.db).await {
             Ok(records) => Ok(records),
-            Err(e) => Err(StorageError::SqlCursorError(e).into()),
+            Err(e) => Err(StorageError::SqlQueryError(e).into()),
         }
     }
 }
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use common::types::relation_db::transaction;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+use crate::relation_db::TransactionHistory;
+
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+const PRUNE_BATCH_ROWS: u64 = 10_000;
+
+/// How long the rows of the transaction history are kept. A row is stale once
+/// it falls out of any of the configured windows, nothing is pruned if none is
+/// configured.
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPolicy {
+    /// Keep the rows of the last `keep_epochs` epochs.
+    pub keep_epochs: Option<u32>,
+    /// Keep the rows of the last `keep_days` days.
+    pub keep_days:   Option<u32>,
+}
+
+impl RetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.keep_epochs.is_some() || self.keep_days.is_some()
+    }
+
+    /// The rows which fall out of any of the configured windows.
+    fn stale_condition(&self, current_epoch: u32, now: u32) -> Condition {
+        let mut condition = Condition::any();
+        if let Some(n) = self.keep_epochs {
+            condition =
+                condition.add(transaction::Column::Epoch.lt(current_epoch.saturating_sub(n)));
+        }
+        if let Some(n) = self.keep_days {
+            condition = condition.add(
+                transaction::Column::Timestamp
+                    .lt(now.saturating_sub(n.saturating_mul(SECONDS_PER_DAY))),
+            );
+        }
+        condition
+    }
+}
+
+impl TransactionHistory {
+    /// Delete the stale rows of the transaction history, in batches of
+    /// `PRUNE_BATCH_ROWS` rows. The amounts of the deleted rows are gone, the
+    /// aggregates of an address only cover the rows which are kept. Returns
+    /// the number of deleted rows.
+    pub async fn prune(&self, policy: &RetentionPolicy, now: u32) -> Result<u64> {
+        if !policy.is_enabled() {
+            return Ok(0);
+        }
+
+        let current_epoch = transaction::Entity::find()
+            .order_by_desc(transaction::Column::Epoch)
+            .one(&self.db)
+            .await?
+            .map(|m| m.epoch)
+            .unwrap_or_default();
+
+        let removed = self
+            .delete_where(policy.stale_condition(current_epoch, now))
+            .await?;
+
+        log::info!(
+            "Pruned {} transaction history rows before epoch {}",
+            removed,
+            current_epoch
+        );
+        Ok(removed)
+    }
+
+    /// Delete the rows of the transaction history below `epoch`, in batches
+    /// of `PRUNE_BATCH_ROWS` rows. Returns the number of deleted rows.
+    pub async fn prune_history_before(&self, epoch: u32) -> Result<u64> {
+        let removed = self
+            .delete_where(Condition::all().add(transaction::Column::Epoch.lt(epoch)))
+            .await?;
+
+        log::info!(
+            "Pruned {} transaction history rows before epoch {}",
+            removed,
+            epoch
+        );
+        Ok(removed)
+    }
+
+    /// Delete the rows matching `stale`, oldest first, committing each batch
+    /// on its own so that concurrent writers are not blocked for the whole
+    /// run.
+    async fn delete_where(&self, stale: Condition) -> Result<u64> {
+        let mut removed = 0;
+        loop {
+            let ids = transaction::Entity::find()
+                .select_only()
+                .column(transaction::Column::Id)
+                .filter(stale.clone())
+                .order_by_asc(transaction::Column::Id)
+                .limit(PRUNE_BATCH_ROWS)
+                .into_tuple::<u32>()
+                .all(&self.db)
+                .await?;
+            if ids.is_empty() {
+                break;
+            }
+
+            let res = transaction::Entity::delete_many()
+                .filter(transaction::Column::Id.is_in(ids))
+                .exec(&self.db)
+                .await?;
+            removed += res.rows_affected;
+        }
+
+        Ok(removed)
+    }
+}
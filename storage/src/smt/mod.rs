@@ -1,13 +1,29 @@
+mod options;
+mod stats;
 mod utils;
 
-use std::{collections::HashMap, fs, path::Path, sync::Arc, vec};
+pub use options::KvdbOptions;
+pub use stats::{CfStats, KvdbStats};
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    vec,
+};
 
 use anyhow::Result;
 use async_trait::async_trait;
 
-use rocksdb::{prelude::*, Direction, IteratorMode, OptimisticTransactionDB};
+use rocksdb::{
+    checkpoint::Checkpoint, prelude::*, ColumnFamily, ColumnFamilyDescriptor, Direction,
+    IteratorMode, OptimisticTransactionDB,
+};
 use smt_rocksdb_store::cf_store::{ColumnFamilyStore, ColumnFamilyStoreMultiTree};
-use sparse_merkle_tree::{blake2b::Blake2bHasher, traits::Value, SparseMerkleTree, H256};
+use sparse_merkle_tree::{
+    blake2b::Blake2bHasher, traits::Value, CompiledMerkleProof, SparseMerkleTree, H256,
+};
 
 use common::{
     traits::smt::{DelegateSmtStorage, ProposalSmtStorage, RewardSmtStorage, StakeSmtStorage},
@@ -18,9 +34,16 @@ use common::{
     },
 };
 
-use crate::error::StorageError;
+use crate::error::{KvdbError, StorageError};
 use crate::{create_table_cfs, get_cf_prefix, get_smt, get_sub_leaves, keys_to_h256};
 
+/// The column family of the bookkeeping of the manager, apart from the SMTs.
+const METADATA_CF: &str = "metadata";
+/// The epoch before which the sub SMTs are pruned.
+const PRUNED_BEFORE_KEY: &[u8] = b"pruned_before";
+/// The keys deleted in one commit when pruning, to bound the memory of it.
+const DELETE_BATCH_KEYS: usize = 10_000;
+
 /// Single SMT
 pub type ColumnFamilyStoreSMT<'a, T, W> =
     SparseMerkleTree<Blake2bHasher, LeafValue, ColumnFamilyStore<'a, T, W>>;
@@ -29,6 +52,14 @@ pub type ColumnFamilyStoreSMT<'a, T, W> =
 pub type ColumnFamilyStoreMultiSMT<'a, T, W> =
     SparseMerkleTree<Blake2bHasher, LeafValue, ColumnFamilyStoreMultiTree<'a, T, W>>;
 
+/// The SMTs in one RocksDB, with a pair of column families per table.
+///
+/// A write of a sub SMT commits together with its root in the top SMT, and a
+/// read works on one snapshot, so a reader never sees a tree half updated.
+/// Several reads are not consistent with each other if a write commits in
+/// between. The writes read the current leaves before they commit and are not
+/// serialized against each other, so the SMTs must have a single writer, while
+/// any number of readers can share the manager through an `Arc`.
 pub struct SmtManager {
     db: Arc<OptimisticTransactionDB>,
 }
@@ -36,41 +67,87 @@ pub struct SmtManager {
 /// SMT manager
 impl SmtManager {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self::new_with_options(path, &KvdbOptions::default())
+    }
+
+    /// Open with the given memtable, compaction, cache and compression
+    /// settings, which apply to every column family.
+    pub fn new_with_options<P: AsRef<Path>>(path: P, options: &KvdbOptions) -> Self {
         if !path.as_ref().is_dir() {
             fs::create_dir_all(path.as_ref())
                 .map_err(StorageError::RocksDBCreationError)
                 .unwrap();
         }
 
-        let mut db_opts = Options::default();
+        let cf_opts = options.to_rocksdb();
+        let mut db_opts = cf_opts.clone();
         db_opts.create_missing_column_families(true);
         db_opts.create_if_missing(true);
 
+        let cfs = Self::cf_names()
+            .into_iter()
+            .map(|name| ColumnFamilyDescriptor::new(name, cf_opts.clone()))
+            .collect::<Vec<_>>();
+        let db = OptimisticTransactionDB::open_cf_descriptors(&db_opts, path, cfs).unwrap();
+
+        Self { db: Arc::new(db) }
+    }
+
+    fn cf_names() -> Vec<String> {
         let mut cfs = vec![];
         cfs.extend_from_slice(create_table_cfs!(STAKER_TABLE));
         cfs.extend_from_slice(create_table_cfs!(DELEGATOR_TABLE));
         cfs.extend_from_slice(create_table_cfs!(REWARD_TABLE));
         cfs.extend_from_slice(create_table_cfs!(PROPOSAL_TABLE));
+        cfs.push(METADATA_CF.to_owned());
+        cfs
+    }
 
-        let db = OptimisticTransactionDB::open_cf(&db_opts, path, cfs).unwrap();
+    /// Flush the memtables of every column family to disk.
+    pub fn flush(&self) -> Result<(), StorageError> {
+        for name in Self::cf_names() {
+            self.db
+                .flush_cf(self.cf(&name)?)
+                .map_err(KvdbError::RocksDB)?;
+        }
+        Ok(())
+    }
 
-        Self { db: Arc::new(db) }
+    /// Create a consistent snapshot of the database at `dst`, which must not
+    /// exist yet. The files are hard-linked when `dst` is on the same file
+    /// system, so this is cheap and can run while the node is writing.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, dst: P) -> Result<PathBuf, StorageError> {
+        let checkpoint = Checkpoint::new(&*self.db).map_err(KvdbError::RocksDB)?;
+        checkpoint
+            .create_checkpoint(dst.as_ref())
+            .map_err(KvdbError::RocksDB)?;
+        Ok(dst.as_ref().to_path_buf())
+    }
+
+    /// Check a proof made by `generate_top_proof` of a top SMT, the stake one
+    /// or a staker's delegate one, against the epochs and their sub roots.
+    pub fn verify_top_proof(root: Root, leaves: Vec<(Epoch, Root)>, proof: Proof) -> Result<bool> {
+        let leaves = leaves
+            .into_iter()
+            .map(|(epoch, sub_root)| {
+                (
+                    SmtKeyEncode::Epoch(epoch).to_h256(),
+                    SmtValueEncode::Root(sub_root).to_leaf_value().to_h256(),
+                )
+            })
+            .collect();
+
+        Ok(CompiledMerkleProof(proof).verify::<Blake2bHasher>(&root, leaves)?)
     }
 
     async fn insert_full_stake(&self, epoch: Epoch, stakers: Vec<(H256, LeafValue)>) -> Result<()> {
-        self.update(
+        self.update_sub_and_top(
             &STAKER_TABLE,
             &SmtPrefixType::Epoch(epoch).as_prefix(),
             stakers,
-        )?;
-
-        let root = StakeSmtStorage::get_sub_root(self, epoch).await?.unwrap();
-        let top_kvs = vec![(
-            SmtKeyEncode::Epoch(epoch).to_h256(),
-            SmtValueEncode::Root(root).to_leaf_value(),
-        )];
-
-        self.update(&STAKER_TABLE, &SmtPrefixType::Top.as_prefix(), top_kvs)
+            &SmtPrefixType::Top.as_prefix(),
+            epoch,
+        )
     }
 
     async fn insert_full_delegate(
@@ -79,27 +156,186 @@ impl SmtManager {
         delegators: HashMap<Staker, Vec<(H256, LeafValue)>>,
     ) -> Result<()> {
         for (staker, amounts) in delegators {
-            let current_prefix = get_cf_prefix!(Epoch, epoch, Address, staker);
-            self.update(&DELEGATOR_TABLE, &current_prefix, amounts)?;
+            self.update_sub_and_top(
+                &DELEGATOR_TABLE,
+                &get_cf_prefix!(Epoch, epoch, Address, staker),
+                amounts,
+                &get_cf_prefix!(Address, staker),
+                epoch,
+            )?;
+        }
+        Ok(())
+    }
 
-            let root = DelegateSmtStorage::get_sub_root(self, epoch, staker)
-                .await?
-                .unwrap();
-            let top_kvs = vec![(
-                SmtKeyEncode::Epoch(epoch).to_h256(),
-                SmtValueEncode::Root(root).to_leaf_value(),
-            )];
+    /// Delete the sub SMTs of the stake, delegate and proposal tables for the
+    /// epochs before `epoch`. The top SMTs are kept as they are, so the top
+    /// roots and the top proofs don't change, but the leaves, sub roots and
+    /// sub proofs of a pruned epoch are no longer available. The epoch is
+    /// recorded first, so a rollback can't land on an epoch which is pruned,
+    /// even partly, and `new_epoch` errors if the previous epoch, which it
+    /// copies, is pruned.
+    pub fn prune_epochs_before(&self, epoch: Epoch) -> Result<(), StorageError> {
+        if self.pruned_before()?.map_or(true, |pruned| pruned < epoch) {
+            self.db
+                .put_cf(
+                    self.cf(METADATA_CF)?,
+                    PRUNED_BEFORE_KEY,
+                    epoch.to_le_bytes(),
+                )
+                .map_err(KvdbError::RocksDB)?;
+        }
 
-            let top_prefix = get_cf_prefix!(Address, staker);
-            self.update(&DELEGATOR_TABLE, &top_prefix, top_kvs)?;
+        for (table, per_address) in [
+            (*STAKER_TABLE, false),
+            (*DELEGATOR_TABLE, true),
+            (*PROPOSAL_TABLE, false),
+        ] {
+            let epochs = self
+                .top_epochs(table, per_address)?
+                .into_iter()
+                .map(|(_, top_epoch)| top_epoch)
+                .filter(|top_epoch| *top_epoch < epoch)
+                .collect::<BTreeSet<_>>();
+
+            // The delegate sub SMTs of an epoch share the epoch prefix, before
+            // the staker address.
+            for pruned in epochs {
+                self.delete_prefix(table, &SmtPrefixType::Epoch(pruned).as_prefix())?;
+            }
         }
         Ok(())
     }
 
-    fn update(&self, cf: &str, prefix: &[u8], kvs: Vec<(H256, LeafValue)>) -> Result<()> {
+    /// The epoch before which the sub SMTs are pruned, if any are.
+    pub fn pruned_before(&self) -> Result<Option<Epoch>, StorageError> {
+        let value = self
+            .db
+            .get_cf(self.cf(METADATA_CF)?, PRUNED_BEFORE_KEY)
+            .map_err(KvdbError::RocksDB)?;
+        value
+            .map(|v| {
+                <[u8; 8]>::try_from(&v[..])
+                    .map(Epoch::from_le_bytes)
+                    .map_err(|_| {
+                        StorageError::from(KvdbError::InvalidValue("pruned_before", v.len()))
+                    })
+            })
+            .transpose()
+    }
+
+    /// An error if the sub SMTs of `epoch` are pruned.
+    fn ensure_not_pruned(&self, epoch: Epoch) -> Result<(), StorageError> {
+        match self.pruned_before()? {
+            Some(pruned) if epoch < pruned => Err(KvdbError::EpochPruned(epoch, pruned).into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The last epoch which has a root in the stake top SMT.
+    pub fn latest_epoch(&self) -> Result<Option<Epoch>, StorageError> {
+        Ok(self
+            .top_epochs(&STAKER_TABLE, false)?
+            .into_iter()
+            .map(|(_, top_epoch)| top_epoch)
+            .max())
+    }
+
+    /// Delete the branches and leaves of the trees under `prefix` in the
+    /// table, committing `DELETE_BATCH_KEYS` keys at a time. The epochs are
+    /// recorded as pruned before, so no read lands on the trees partly
+    /// deleted.
+    fn delete_prefix(&self, table: &str, prefix: &[u8]) -> Result<(), StorageError> {
+        for suffix in [CFSuffixType::Branch, CFSuffixType::Leaf] {
+            let cf = self.cf(&format!("{}_{}", table, suffix))?;
+            loop {
+                let mode = IteratorMode::From(prefix, Direction::Forward);
+                let keys = self
+                    .db
+                    .get_iter_cf(cf, &ReadOptions::default(), mode)
+                    .map_err(KvdbError::RocksDB)?
+                    .into_iter()
+                    .map(|(k, _)| k)
+                    .take_while(|k| k.starts_with(prefix))
+                    .take(DELETE_BATCH_KEYS)
+                    .collect::<Vec<_>>();
+                if keys.is_empty() {
+                    break;
+                }
+                let inner = self.db.transaction_default();
+                for key in keys {
+                    inner.delete_cf(cf, key).map_err(KvdbError::RocksDB)?;
+                }
+                inner.commit().map_err(KvdbError::RocksDB)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The epochs which have a root in a top SMT of the table, with the
+    /// address of the top SMT if the table has one per address.
+    fn top_epochs(
+        &self,
+        table: &str,
+        per_address: bool,
+    ) -> Result<Vec<(Option<Address>, Epoch)>, StorageError> {
+        let top_prefix = SmtPrefixType::Top.as_prefix();
+        let address_len = if per_address { Address::len_bytes() } else { 0 };
+        let key_len = top_prefix.len() + address_len + 32;
+
+        let cf = self.cf(&format!("{}_{}", table, CFSuffixType::Leaf))?;
+        let mode = IteratorMode::From(&top_prefix, Direction::Forward);
+        let cf_iter = self
+            .db
+            .get_iter_cf(cf, &ReadOptions::default(), mode)
+            .map_err(KvdbError::RocksDB)?;
+
+        Ok(cf_iter
+            .into_iter()
+            .take_while(|(k, _)| k.starts_with(&top_prefix))
+            .filter(|(k, v)| k.len() == key_len && v.iter().any(|b| *b != 0))
+            .map(|(k, _)| {
+                let address =
+                    per_address.then(|| Address::from_slice(&k[top_prefix.len()..key_len - 32]));
+                let leaf_epoch = Epoch::from_le_bytes(
+                    k[key_len - 32..key_len - 24]
+                        .try_into()
+                        .expect("checked 8 bytes"),
+                );
+                (address, leaf_epoch)
+            })
+            .collect())
+    }
+
+    /// The handle of a column family, an error instead of a panic if the
+    /// database was opened without it.
+    pub(crate) fn cf(&self, name: &str) -> Result<&ColumnFamily, StorageError> {
+        self.db
+            .cf_handle(name)
+            .ok_or_else(|| KvdbError::MissingColumnFamily(name.to_owned()).into())
+    }
+
+    /// Update a sub SMT and its root in the top SMT in one transaction, so
+    /// that a reader sees both or neither.
+    fn update_sub_and_top(
+        &self,
+        table: &str,
+        sub_prefix: &[u8],
+        kvs: Vec<(H256, LeafValue)>,
+        top_prefix: &[u8],
+        epoch: Epoch,
+    ) -> Result<()> {
         let inner = self.db.transaction_default();
-        let mut smt = get_smt!(self.db, cf, prefix, &inner);
-        smt.update_all(kvs)?;
+        let root = {
+            let mut smt = get_smt!(self, table, sub_prefix, &inner);
+            smt.update_all(kvs)?;
+            *smt.root()
+        };
+
+        let mut top_smt = get_smt!(self, table, top_prefix, &inner);
+        top_smt.update(
+            SmtKeyEncode::Epoch(epoch).to_h256(),
+            SmtValueEncode::Root(root).to_leaf_value(),
+        )?;
         inner.commit()?;
         Ok(())
     }
@@ -132,36 +368,60 @@ impl StakeSmtStorage for SmtManager {
         if epoch == 0 {
             return Ok(());
         }
+        self.ensure_not_pruned(epoch - 1)?;
 
-        let stakers = StakeSmtStorage::get_sub_leaves(self, epoch - 1)
+        // Clear the epoch in the same update so that re-applying a transition
+        // overwrites the snapshot instead of merging into it.
+        let mut stakers = StakeSmtStorage::get_sub_leaves(self, epoch)
             .await?
-            .into_iter()
-            .map(|(k, v)| {
-                (
-                    SmtKeyEncode::Address(k).to_h256(),
-                    SmtValueEncode::Amount(v).to_leaf_value(),
-                )
-            })
-            .collect();
+            .into_keys()
+            .map(|k| (SmtKeyEncode::Address(k).to_h256(), LeafValue::zero()))
+            .collect::<HashMap<_, _>>();
+        stakers.extend(
+            StakeSmtStorage::get_sub_leaves(self, epoch - 1)
+                .await?
+                .into_iter()
+                .map(|(k, v)| {
+                    (
+                        SmtKeyEncode::Address(k).to_h256(),
+                        SmtValueEncode::Amount(v).to_leaf_value(),
+                    )
+                }),
+        );
 
-        self.insert_full_stake(epoch, stakers).await
+        self.insert_full_stake(epoch, stakers.into_iter().collect())
+            .await
     }
 
-    async fn insert(&self, epoch: Epoch, stakers: Vec<UserAmount>) -> Result<()> {
-        let leaves = StakeSmtStorage::get_sub_leaves(self, epoch).await?;
-        StakeSmtStorage::remove(self, epoch, leaves.into_keys().collect()).await?;
+    async fn rollback_to_epoch(&self, epoch: Epoch) -> Result<()> {
+        self.ensure_not_pruned(epoch)?;
+        let later_epochs = self
+            .top_epochs(&STAKER_TABLE, false)?
+            .into_iter()
+            .filter(|(_, top_epoch)| *top_epoch > epoch);
+        for (_, later_epoch) in later_epochs {
+            let stakers = StakeSmtStorage::get_sub_leaves(self, later_epoch).await?;
+            StakeSmtStorage::remove(self, later_epoch, stakers.into_keys().collect()).await?;
+        }
+        Ok(())
+    }
 
-        let new_stakers = stakers
-            .iter()
-            .map(|s| {
-                (
-                    SmtKeyEncode::Address(s.user).to_h256(),
-                    SmtValueEncode::Amount(s.amount).to_leaf_value(),
-                )
-            })
-            .collect();
+    async fn insert(&self, epoch: Epoch, stakers: Vec<UserAmount>) -> Result<()> {
+        // The stakers which are not given any more are cleared.
+        let mut new_stakers = StakeSmtStorage::get_sub_leaves(self, epoch)
+            .await?
+            .into_keys()
+            .map(|k| (SmtKeyEncode::Address(k).to_h256(), LeafValue::zero()))
+            .collect::<HashMap<_, _>>();
+        new_stakers.extend(stakers.iter().map(|s| {
+            (
+                SmtKeyEncode::Address(s.user).to_h256(),
+                SmtValueEncode::Amount(s.amount).to_leaf_value(),
+            )
+        }));
 
-        self.insert_full_stake(epoch, new_stakers).await
+        self.insert_full_stake(epoch, new_stakers.into_iter().collect())
+            .await
     }
 
     async fn remove(&self, epoch: Epoch, stakers: Vec<Staker>) -> Result<()> {
@@ -176,7 +436,7 @@ impl StakeSmtStorage for SmtManager {
     async fn get_amount(&self, epoch: Epoch, staker: Staker) -> Result<Option<Amount>> {
         let prefix = SmtPrefixType::Epoch(epoch).as_prefix();
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &STAKER_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &STAKER_TABLE, &prefix, &snapshot);
 
         let leaf_value = smt.get(&SmtKeyEncode::Address(staker).to_h256())?;
         if leaf_value == LeafValue::zero() {
@@ -186,13 +446,22 @@ impl StakeSmtStorage for SmtManager {
         Ok(Some(Amount::from(leaf_value)))
     }
 
+    async fn contains(&self, epoch: Epoch, staker: Staker) -> Result<bool> {
+        let prefix = SmtPrefixType::Epoch(epoch).as_prefix();
+        let snapshot = self.db.snapshot();
+        let smt = get_smt!(self, &STAKER_TABLE, &prefix, &snapshot);
+
+        let leaf_value = smt.get(&SmtKeyEncode::Address(staker).to_h256())?;
+        Ok(leaf_value != LeafValue::zero())
+    }
+
     async fn get_sub_leaves(&self, epoch: Epoch) -> Result<HashMap<Staker, Amount>> {
         let prefix = SmtPrefixType::Epoch(epoch).as_prefix();
 
         Ok(get_sub_leaves!(
             Amount,
             &prefix,
-            self.db,
+            self,
             STAKER_TABLE.to_string()
         ))
     }
@@ -200,7 +469,7 @@ impl StakeSmtStorage for SmtManager {
     async fn get_sub_root(&self, epoch: Epoch) -> Result<Option<Root>> {
         let prefix = SmtPrefixType::Epoch(epoch).as_prefix();
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &STAKER_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &STAKER_TABLE, &prefix, &snapshot);
 
         Ok(Some(*smt.root()))
     }
@@ -219,16 +488,31 @@ impl StakeSmtStorage for SmtManager {
     async fn get_top_root(&self) -> Result<Root> {
         let prefix = SmtPrefixType::Top.as_prefix();
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &STAKER_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &STAKER_TABLE, &prefix, &snapshot);
 
         Ok(*smt.root())
     }
 
+    async fn root_history(&self, start: Epoch, end: Epoch) -> Result<Vec<(Epoch, Root)>> {
+        let prefix = SmtPrefixType::Top.as_prefix();
+        let snapshot = self.db.snapshot();
+        let smt = get_smt!(self, &STAKER_TABLE, &prefix, &snapshot);
+
+        let mut roots = vec![];
+        for epoch in start..=end {
+            let leaf_value = smt.get(&SmtKeyEncode::Epoch(epoch).to_h256())?;
+            if leaf_value != LeafValue::zero() {
+                roots.push((epoch, leaf_value.to_h256()));
+            }
+        }
+        Ok(roots)
+    }
+
     async fn generate_sub_proof(&self, epoch: Epoch, stakers: Vec<Staker>) -> Result<Proof> {
         let prefix = SmtPrefixType::Epoch(epoch).as_prefix();
         let snapshot = self.db.snapshot();
         let keys = keys_to_h256!(stakers, Address);
-        let smt = get_smt!(self.db, &STAKER_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &STAKER_TABLE, &prefix, &snapshot);
 
         Ok(smt.merkle_proof(keys.clone())?.compile(keys)?.into())
     }
@@ -237,7 +521,7 @@ impl StakeSmtStorage for SmtManager {
         let prefix = SmtPrefixType::Top.as_prefix();
         let snapshot = self.db.snapshot();
         let keys = keys_to_h256!(epochs, Epoch);
-        let smt = get_smt!(self.db, &STAKER_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &STAKER_TABLE, &prefix, &snapshot);
 
         Ok(smt.merkle_proof(keys.clone())?.compile(keys)?.into())
     }
@@ -281,6 +565,7 @@ impl DelegateSmtStorage for SmtManager {
         if epoch == 0 {
             return Ok(());
         }
+        self.ensure_not_pruned(epoch - 1)?;
 
         let stakers = StakeSmtStorage::get_sub_leaves(self, epoch - 1)
             .await?
@@ -290,44 +575,73 @@ impl DelegateSmtStorage for SmtManager {
         let mut delegators = HashMap::with_capacity(stakers.len());
 
         for staker in stakers {
-            let kvs = DelegateSmtStorage::get_sub_leaves(self, epoch - 1, staker)
+            let mut kvs = DelegateSmtStorage::get_sub_leaves(self, epoch, staker)
                 .await?
-                .into_iter()
-                .map(|(k, v)| {
-                    (
-                        SmtKeyEncode::Address(k).to_h256(),
-                        SmtValueEncode::Amount(v).to_leaf_value(),
-                    )
-                })
-                .collect();
-            delegators.insert(staker, kvs);
+                .into_keys()
+                .map(|k| (SmtKeyEncode::Address(k).to_h256(), LeafValue::zero()))
+                .collect::<HashMap<_, _>>();
+            kvs.extend(
+                DelegateSmtStorage::get_sub_leaves(self, epoch - 1, staker)
+                    .await?
+                    .into_iter()
+                    .map(|(k, v)| {
+                        (
+                            SmtKeyEncode::Address(k).to_h256(),
+                            SmtValueEncode::Amount(v).to_leaf_value(),
+                        )
+                    }),
+            );
+            delegators.insert(staker, kvs.into_iter().collect());
         }
 
         self.insert_full_delegate(epoch, delegators).await
     }
 
+    async fn rollback_to_epoch(&self, epoch: Epoch) -> Result<()> {
+        self.ensure_not_pruned(epoch)?;
+        let later_epochs = self
+            .top_epochs(&DELEGATOR_TABLE, true)?
+            .into_iter()
+            .filter(|(_, top_epoch)| *top_epoch > epoch);
+        for (staker, later_epoch) in later_epochs {
+            let staker = staker.expect("per address top SMT");
+            let leaves = DelegateSmtStorage::get_sub_leaves(self, later_epoch, staker).await?;
+            let delegators = leaves.into_keys().map(|k| (staker, k)).collect();
+            DelegateSmtStorage::remove(self, later_epoch, delegators).await?;
+        }
+        Ok(())
+    }
+
     async fn insert(
         &self,
         epoch: Epoch,
         staker: Staker,
         delegators: Vec<UserAmount>,
     ) -> Result<()> {
-        let leaves = DelegateSmtStorage::get_sub_leaves(self, epoch, staker).await?;
-        let old_delegators = leaves.into_keys().map(|k| (staker, k)).collect();
-        DelegateSmtStorage::remove(self, epoch, old_delegators).await?;
+        DelegateSmtStorage::insert_many(self, epoch, HashMap::from([(staker, delegators)])).await
+    }
 
-        let kvs = delegators
-            .iter()
-            .map(|s| {
+    async fn insert_many(
+        &self,
+        epoch: Epoch,
+        entries: HashMap<Staker, Vec<UserAmount>>,
+    ) -> Result<()> {
+        let mut new_delegators = HashMap::with_capacity(entries.len());
+        for (staker, delegators) in entries {
+            // The delegators which are not given any more are cleared.
+            let mut kvs = DelegateSmtStorage::get_sub_leaves(self, epoch, staker)
+                .await?
+                .into_keys()
+                .map(|k| (SmtKeyEncode::Address(k).to_h256(), LeafValue::zero()))
+                .collect::<HashMap<_, _>>();
+            kvs.extend(delegators.iter().map(|s| {
                 (
                     SmtKeyEncode::Address(s.user).to_h256(),
                     SmtValueEncode::Amount(s.amount).to_leaf_value(),
                 )
-            })
-            .collect();
-
-        let mut new_delegators = HashMap::with_capacity(1);
-        new_delegators.insert(staker, kvs);
+            }));
+            new_delegators.insert(staker, kvs.into_iter().collect());
+        }
 
         self.insert_full_delegate(epoch, new_delegators).await
     }
@@ -357,7 +671,7 @@ impl DelegateSmtStorage for SmtManager {
         let prefix = get_cf_prefix!(Epoch, epoch, Address, staker);
 
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &DELEGATOR_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
 
         let leaf_value = smt.get(&SmtKeyEncode::Address(delegator).to_h256())?;
         if leaf_value == LeafValue::zero() {
@@ -367,6 +681,34 @@ impl DelegateSmtStorage for SmtManager {
         Ok(Some(Amount::from(leaf_value)))
     }
 
+    async fn get_amounts(
+        &self,
+        epoch: Epoch,
+        staker: Staker,
+        delegators: &[Delegator],
+    ) -> Result<Vec<Option<Amount>>> {
+        let prefix = get_cf_prefix!(Epoch, epoch, Address, staker);
+
+        let snapshot = self.db.snapshot();
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
+
+        let mut amounts = Vec::with_capacity(delegators.len());
+        for delegator in delegators {
+            let leaf_value = smt.get(&SmtKeyEncode::Address(*delegator).to_h256())?;
+            amounts.push((leaf_value != LeafValue::zero()).then(|| Amount::from(leaf_value)));
+        }
+        Ok(amounts)
+    }
+
+    async fn contains(&self, epoch: Epoch, staker: Staker, delegator: Delegator) -> Result<bool> {
+        let prefix = get_cf_prefix!(Epoch, epoch, Address, staker);
+        let snapshot = self.db.snapshot();
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
+
+        let leaf_value = smt.get(&SmtKeyEncode::Address(delegator).to_h256())?;
+        Ok(leaf_value != LeafValue::zero())
+    }
+
     async fn get_sub_leaves(
         &self,
         epoch: Epoch,
@@ -377,7 +719,7 @@ impl DelegateSmtStorage for SmtManager {
         Ok(get_sub_leaves!(
             Amount,
             &prefix,
-            self.db,
+            self,
             DELEGATOR_TABLE.to_string()
         ))
     }
@@ -386,7 +728,7 @@ impl DelegateSmtStorage for SmtManager {
         let prefix = get_cf_prefix!(Epoch, epoch, Address, staker);
 
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &DELEGATOR_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
 
         Ok(Some(*smt.root()))
     }
@@ -409,11 +751,28 @@ impl DelegateSmtStorage for SmtManager {
     async fn get_top_root(&self, staker: Staker) -> Result<Root> {
         let prefix = get_cf_prefix!(Address, staker);
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &DELEGATOR_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
 
         Ok(*smt.root())
     }
 
+    async fn get_delegate_root_at_epoch(
+        &self,
+        staker: Staker,
+        epoch: Epoch,
+    ) -> Result<Option<Root>> {
+        let prefix = get_cf_prefix!(Address, staker);
+        let snapshot = self.db.snapshot();
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
+
+        let leaf_value = smt.get(&SmtKeyEncode::Epoch(epoch).to_h256())?;
+        if leaf_value == LeafValue::zero() {
+            return Ok(None);
+        }
+
+        Ok(Some(Root::from(leaf_value)))
+    }
+
     async fn get_top_roots(&self, stakers: Vec<Staker>) -> Result<HashMap<Staker, Root>> {
         let mut hash_map = HashMap::with_capacity(stakers.len());
         for staker in stakers {
@@ -435,7 +794,7 @@ impl DelegateSmtStorage for SmtManager {
         let snapshot = self.db.snapshot();
         let keys = keys_to_h256!(delegators, Address);
 
-        let smt = get_smt!(self.db, &DELEGATOR_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
 
         Ok(smt.merkle_proof(keys.clone())?.compile(keys)?.into())
     }
@@ -445,7 +804,7 @@ impl DelegateSmtStorage for SmtManager {
 
         let snapshot = self.db.snapshot();
         let keys = keys_to_h256!(epochs, Epoch);
-        let smt = get_smt!(self.db, &DELEGATOR_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &DELEGATOR_TABLE, &prefix, &snapshot);
 
         Ok(smt.merkle_proof(keys.clone())?.compile(keys)?.into())
     }
@@ -474,7 +833,7 @@ impl RewardSmtStorage for SmtManager {
         )];
 
         let inner = self.db.transaction_default();
-        let mut smt = get_smt!(self.db, &REWARD_TABLE, &inner);
+        let mut smt = get_smt!(self, &REWARD_TABLE, &inner);
         smt.update_all(kvs)?;
         inner.commit()?;
         Ok(())
@@ -482,14 +841,14 @@ impl RewardSmtStorage for SmtManager {
 
     async fn get_root(&self) -> Result<Root> {
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &REWARD_TABLE, &snapshot);
+        let smt = get_smt!(self, &REWARD_TABLE, &snapshot);
 
         Ok(*smt.root())
     }
 
     async fn get_epoch(&self, address: Address) -> Result<Option<Epoch>> {
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &REWARD_TABLE, &snapshot);
+        let smt = get_smt!(self, &REWARD_TABLE, &snapshot);
 
         let leaf_value = smt.get(&SmtKeyEncode::Address(address).to_h256())?;
         if leaf_value == LeafValue::zero() {
@@ -501,7 +860,7 @@ impl RewardSmtStorage for SmtManager {
 
     async fn generate_proof(&self, addresses: Vec<Address>) -> Result<Proof> {
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &REWARD_TABLE, &snapshot);
+        let smt = get_smt!(self, &REWARD_TABLE, &snapshot);
 
         let mut keys = Vec::with_capacity(addresses.len());
         for address in addresses {
@@ -546,27 +905,19 @@ impl ProposalSmtStorage for SmtManager {
             })
             .collect();
 
-        self.update(
+        self.update_sub_and_top(
             &PROPOSAL_TABLE,
             &SmtPrefixType::Epoch(epoch).as_prefix(),
             kvs,
-        )?;
-
-        let root = ProposalSmtStorage::get_sub_root(self, epoch)
-            .await?
-            .unwrap();
-        let top_kvs = vec![(
-            SmtKeyEncode::Epoch(epoch).to_h256(),
-            SmtValueEncode::Root(root).to_leaf_value(),
-        )];
-
-        self.update(&PROPOSAL_TABLE, &SmtPrefixType::Top.as_prefix(), top_kvs)
+            &SmtPrefixType::Top.as_prefix(),
+            epoch,
+        )
     }
 
     async fn get_count(&self, epoch: Epoch, validator: Address) -> Result<Option<ProposalCount>> {
         let prefix = SmtPrefixType::Epoch(epoch).as_prefix();
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &PROPOSAL_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &PROPOSAL_TABLE, &prefix, &snapshot);
 
         let leaf_value = smt.get(&SmtKeyEncode::Address(validator).to_h256())?;
         if leaf_value == LeafValue::zero() {
@@ -582,7 +933,7 @@ impl ProposalSmtStorage for SmtManager {
         Ok(get_sub_leaves!(
             ProposalCount,
             &prefix,
-            self.db,
+            self,
             PROPOSAL_TABLE.to_string()
         ))
     }
@@ -590,7 +941,7 @@ impl ProposalSmtStorage for SmtManager {
     async fn get_sub_root(&self, epoch: Epoch) -> Result<Option<Root>> {
         let prefix = SmtPrefixType::Epoch(epoch).as_prefix();
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &PROPOSAL_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &PROPOSAL_TABLE, &prefix, &snapshot);
 
         Ok(Some(*smt.root()))
     }
@@ -609,7 +960,7 @@ impl ProposalSmtStorage for SmtManager {
     async fn get_top_root(&self) -> Result<Root> {
         let prefix = SmtPrefixType::Top.as_prefix();
         let snapshot = self.db.snapshot();
-        let smt = get_smt!(self.db, &PROPOSAL_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &PROPOSAL_TABLE, &prefix, &snapshot);
         Ok(*smt.root())
     }
 
@@ -618,7 +969,7 @@ impl ProposalSmtStorage for SmtManager {
         let snapshot = self.db.snapshot();
         let keys = keys_to_h256!(validators, Address);
 
-        let smt = get_smt!(self.db, &PROPOSAL_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &PROPOSAL_TABLE, &prefix, &snapshot);
 
         Ok(smt.merkle_proof(keys.clone())?.compile(keys)?.into())
     }
@@ -628,7 +979,7 @@ impl ProposalSmtStorage for SmtManager {
         let snapshot = self.db.snapshot();
         let keys = keys_to_h256!(epochs, Epoch);
 
-        let smt = get_smt!(self.db, &PROPOSAL_TABLE, &prefix, &snapshot);
+        let smt = get_smt!(self, &PROPOSAL_TABLE, &prefix, &snapshot);
 
         Ok(smt.merkle_proof(keys.clone())?.compile(keys)?.into())
     }
@@ -0,0 +1,54 @@
+use rocksdb::{BlockBasedOptions, Cache, DBCompressionType, Options};
+
+const MIB: usize = 1024 * 1024;
+
+/// The RocksDB tuning of the SMT database, the library defaults are kept for
+/// the options which are not set.
+#[derive(Clone, Debug, Default)]
+pub struct KvdbOptions {
+    /// The size of a memtable before it is flushed, in bytes.
+    pub write_buffer_size:   Option<usize>,
+    /// The number of the concurrent flush and compaction jobs.
+    pub max_background_jobs: Option<i32>,
+    /// The size of the LRU block cache shared by the column families, in
+    /// bytes.
+    pub block_cache_size:    Option<usize>,
+    /// Compress the blocks with snappy.
+    pub compression:         Option<bool>,
+}
+
+impl KvdbOptions {
+    /// A preset for the write-heavy workload of the epoch updates: large
+    /// memtables and more background jobs to keep compaction up.
+    pub fn sync() -> Self {
+        KvdbOptions {
+            write_buffer_size:   Some(128 * MIB),
+            max_background_jobs: Some(4),
+            block_cache_size:    Some(256 * MIB),
+            compression:         Some(true),
+        }
+    }
+
+    pub(crate) fn to_rocksdb(&self) -> Options {
+        let mut opts = Options::default();
+        if let Some(size) = self.write_buffer_size {
+            opts.set_write_buffer_size(size);
+        }
+        if let Some(jobs) = self.max_background_jobs {
+            opts.set_max_background_jobs(jobs);
+        }
+        if let Some(size) = self.block_cache_size {
+            let mut block_opts = BlockBasedOptions::default();
+            block_opts.set_block_cache(&Cache::new_lru_cache(size));
+            opts.set_block_based_table_factory(&block_opts);
+        }
+        if let Some(compression) = self.compression {
+            opts.set_compression_type(if compression {
+                DBCompressionType::Snappy
+            } else {
+                DBCompressionType::None
+            });
+        }
+        opts
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+
+use rocksdb::prelude::*;
+
+use crate::smt::SmtManager;
+
+const ESTIMATE_NUM_KEYS: &str = "rocksdb.estimate-num-keys";
+const ESTIMATE_LIVE_DATA_SIZE: &str = "rocksdb.estimate-live-data-size";
+const PENDING_COMPACTION_BYTES: &str = "rocksdb.estimate-pending-compaction-bytes";
+
+/// The RocksDB estimates of the SMT database per column family, for
+/// monitoring.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvdbStats {
+    pub column_families: BTreeMap<String, CfStats>,
+}
+
+/// A field is `None` if RocksDB doesn't report the property.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CfStats {
+    pub estimate_num_keys:        Option<u64>,
+    pub estimate_live_data_size:  Option<u64>,
+    pub pending_compaction_bytes: Option<u64>,
+}
+
+impl SmtManager {
+    pub fn stats(&self) -> KvdbStats {
+        let column_families = Self::cf_names()
+            .into_iter()
+            .filter_map(|name| {
+                let cf = self.cf(&name).ok()?;
+                let property = |key| self.db.property_int_value_cf(cf, key).ok().flatten();
+                let stats = CfStats {
+                    estimate_num_keys:        property(ESTIMATE_NUM_KEYS),
+                    estimate_live_data_size:  property(ESTIMATE_LIVE_DATA_SIZE),
+                    pending_compaction_bytes: property(PENDING_COMPACTION_BYTES),
+                };
+                Some((name, stats))
+            })
+            .collect();
+
+        KvdbStats { column_families }
+    }
+}
@@ -37,12 +37,12 @@ macro_rules! keys_to_h256 {
 
 #[macro_export]
 macro_rules! get_smt {
-    ($db: expr, $cf: expr, $prefix: expr, $inner: expr) => {{
+    ($smt: expr, $cf: expr, $prefix: expr, $inner: expr) => {{
         let cf1 = format!("{}_{}", $cf.to_string(), CFSuffixType::Branch);
         let cf2 = format!("{}_{}", $cf.to_string(), CFSuffixType::Leaf);
 
-        let cf1_handle = $db.cf_handle(&cf1).unwrap();
-        let cf2_handle = $db.cf_handle(&cf2).unwrap();
+        let cf1_handle = $smt.cf(&cf1)?;
+        let cf2_handle = $smt.cf(&cf2)?;
 
         let smt = ColumnFamilyStoreMultiSMT::new_with_store(
             ColumnFamilyStoreMultiTree::<_, ()>::new($prefix, $inner, cf1_handle, cf2_handle),
@@ -52,12 +52,12 @@ macro_rules! get_smt {
         smt
     }};
 
-    ($db: expr, $cf: expr, $inner: expr) => {{
+    ($smt: expr, $cf: expr, $inner: expr) => {{
         let cf1 = format!("{}_{}", $cf.to_string(), CFSuffixType::Branch);
         let cf2 = format!("{}_{}", $cf.to_string(), CFSuffixType::Leaf);
 
-        let cf1_handle = $db.cf_handle(&cf1).unwrap();
-        let cf2_handle = $db.cf_handle(&cf2).unwrap();
+        let cf1_handle = $smt.cf(&cf1)?;
+        let cf2_handle = $smt.cf(&cf2)?;
 
         let smt = ColumnFamilyStoreSMT::new_with_store(ColumnFamilyStore::<_, ()>::new(
             $inner, cf1_handle, cf2_handle,
@@ -70,17 +70,20 @@ macro_rules! get_smt {
 
 #[macro_export]
 macro_rules! get_sub_leaves {
-    ($ty: ty, $prefix: expr, $db: expr, $table: expr) => {{
+    ($ty: ty, $prefix: expr, $smt: expr, $table: expr) => {{
         let prefix_len = $prefix.len();
         let key_len = prefix_len + 32;
         let mode = IteratorMode::From($prefix, Direction::Forward);
         let read_opt = ReadOptions::default();
-        let cf = $db
-            .cf_handle(&format!("{}_{}", $table, CFSuffixType::Leaf))
-            .unwrap();
-        let cf_iter = $db.get_iter_cf(cf, &read_opt, mode).unwrap();
+        let cf = $smt.cf(&format!("{}_{}", $table, CFSuffixType::Leaf))?;
+        let cf_iter = $smt
+            .db
+            .get_iter_cf(cf, &read_opt, mode)
+            .map_err(|e| StorageError::from(KvdbError::from(e)))?;
+        // The trees are stored one after another, stop at the end of this one.
         cf_iter
             .into_iter()
+            .take_while(|(k, _)| k.starts_with($prefix))
             .filter_map(|(k, v)| {
                 if key_len != k.len() {
                     None
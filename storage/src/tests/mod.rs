@@ -1,11 +1,22 @@
-use std::{path::PathBuf, vec};
+mod relation_db;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    vec,
+};
 
 use common::{
     traits::smt::{DelegateSmtStorage, ProposalSmtStorage, RewardSmtStorage, StakeSmtStorage},
-    types::smt::UserAmount,
+    types::smt::{Delegator, Root, Staker, UserAmount},
 };
 
-use super::smt::SmtManager;
+use super::smt::{KvdbOptions, SmtManager};
+use crate::error::{KvdbError, StorageError};
 
 static ROCKSDB_PATH: &str = "./free-space/smt";
 
@@ -64,6 +75,14 @@ async fn test_stake_functions() {
         .await
         .unwrap();
     assert_eq!(result, None);
+
+    // contains
+    assert!(!StakeSmtStorage::contains(&smt_manager, epoch, staker)
+        .await
+        .unwrap());
+    assert!(StakeSmtStorage::contains(&smt_manager, epoch + 1, staker)
+        .await
+        .unwrap());
 }
 
 #[tokio::test]
@@ -123,6 +142,550 @@ async fn test_delegate_functions() {
         .await
         .unwrap();
     assert_eq!(result, None);
+
+    // contains
+    assert!(
+        !DelegateSmtStorage::contains(&smt_manager, epoch, staker, delegator)
+            .await
+            .unwrap()
+    );
+    assert!(
+        DelegateSmtStorage::contains(&smt_manager, epoch + 1, staker, delegator)
+            .await
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_missing_column_family() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("column_family");
+    let smt_manager = SmtManager::new(path);
+
+    assert!(smt_manager.cf("staker_leaf").is_ok());
+    assert!(matches!(
+        smt_manager.cf("missing"),
+        Err(StorageError::Kvdb(KvdbError::MissingColumnFamily(name))) if name == "missing"
+    ));
+}
+
+#[tokio::test]
+async fn test_kvdb_options() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("options");
+    let staker = [5u8; 20].into();
+
+    {
+        let smt_manager = SmtManager::new_with_options(&path, &KvdbOptions::sync());
+        StakeSmtStorage::insert(&smt_manager, 1, vec![UserAmount {
+            user:        staker,
+            amount:      100,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+    }
+
+    let smt_manager = SmtManager::new_with_options(&path, &KvdbOptions {
+        compression: Some(false),
+        ..Default::default()
+    });
+    let amount = StakeSmtStorage::get_amount(&smt_manager, 1, staker)
+        .await
+        .unwrap();
+    assert_eq!(amount, Some(100));
+}
+
+#[tokio::test]
+async fn test_checkpoint() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("checkpoint");
+    let mut dst = PathBuf::from(ROCKSDB_PATH);
+    dst.push("checkpoint_backup");
+    let _ = std::fs::remove_dir_all(&dst);
+
+    let smt_manager = SmtManager::new(path);
+    let staker = [5u8; 20].into();
+    StakeSmtStorage::insert(&smt_manager, 1, vec![UserAmount {
+        user:        staker,
+        amount:      100,
+        is_increase: true,
+    }])
+    .await
+    .unwrap();
+    smt_manager.flush().unwrap();
+
+    let created = smt_manager.create_checkpoint(&dst).unwrap();
+    assert_eq!(created, dst);
+    assert!(smt_manager.create_checkpoint(&dst).is_err());
+
+    let backup = SmtManager::new(dst);
+    let amount = StakeSmtStorage::get_amount(&backup, 1, staker)
+        .await
+        .unwrap();
+    assert_eq!(amount, Some(100));
+}
+
+#[tokio::test]
+async fn test_kvdb_stats() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("stats");
+    let smt_manager = SmtManager::new(path);
+    StakeSmtStorage::insert(&smt_manager, 1, vec![UserAmount {
+        user:        [5u8; 20].into(),
+        amount:      100,
+        is_increase: true,
+    }])
+    .await
+    .unwrap();
+
+    let stats = smt_manager.stats();
+    assert_eq!(stats.column_families.len(), 9);
+    let leaf = &stats.column_families["staker_leaf"];
+    assert!(leaf.estimate_num_keys.unwrap() > 0);
+}
+
+#[tokio::test]
+async fn test_rollback_to_epoch() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("rollback");
+    let smt_manager = SmtManager::new(path);
+    let staker = [5u8; 20].into();
+    let delegator = [6u8; 20].into();
+    let amounts = |amount| {
+        vec![UserAmount {
+            user: staker,
+            amount,
+            is_increase: true,
+        }]
+    };
+
+    for epoch in 1..=3 {
+        StakeSmtStorage::insert(&smt_manager, epoch, amounts(epoch as u128 * 10))
+            .await
+            .unwrap();
+        DelegateSmtStorage::insert(&smt_manager, epoch, staker, vec![UserAmount {
+            user:        delegator,
+            amount:      epoch as u128,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+    }
+    let stake_root = StakeSmtStorage::get_sub_root(&smt_manager, 1)
+        .await
+        .unwrap();
+
+    StakeSmtStorage::rollback_to_epoch(&smt_manager, 1)
+        .await
+        .unwrap();
+    DelegateSmtStorage::rollback_to_epoch(&smt_manager, 1)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        StakeSmtStorage::get_amount(&smt_manager, 1, staker)
+            .await
+            .unwrap(),
+        Some(10)
+    );
+    assert_eq!(
+        StakeSmtStorage::get_sub_root(&smt_manager, 1)
+            .await
+            .unwrap(),
+        stake_root
+    );
+    assert_eq!(
+        DelegateSmtStorage::get_amount(&smt_manager, 1, staker, delegator)
+            .await
+            .unwrap(),
+        Some(1)
+    );
+    for epoch in 2..=3 {
+        assert!(!StakeSmtStorage::contains(&smt_manager, epoch, staker)
+            .await
+            .unwrap());
+        assert!(
+            !DelegateSmtStorage::contains(&smt_manager, epoch, staker, delegator)
+                .await
+                .unwrap()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_prune_epochs_before() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("prune");
+    let smt_manager = SmtManager::new(path);
+    let staker = [5u8; 20].into();
+    let delegator = [6u8; 20].into();
+
+    for epoch in 1..=3 {
+        StakeSmtStorage::insert(&smt_manager, epoch, vec![UserAmount {
+            user:        staker,
+            amount:      epoch as u128 * 10,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+        DelegateSmtStorage::insert(&smt_manager, epoch, staker, vec![UserAmount {
+            user:        delegator,
+            amount:      epoch as u128,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+    }
+    let stake_root = StakeSmtStorage::get_top_root(&smt_manager).await.unwrap();
+    let delegate_root = DelegateSmtStorage::get_top_root(&smt_manager, staker)
+        .await
+        .unwrap();
+
+    smt_manager.prune_epochs_before(3).unwrap();
+
+    assert_eq!(smt_manager.latest_epoch().unwrap(), Some(3));
+    assert_eq!(
+        StakeSmtStorage::get_top_root(&smt_manager).await.unwrap(),
+        stake_root
+    );
+    assert_eq!(
+        DelegateSmtStorage::get_top_root(&smt_manager, staker)
+            .await
+            .unwrap(),
+        delegate_root
+    );
+    for epoch in 1..=2 {
+        assert!(StakeSmtStorage::get_sub_leaves(&smt_manager, epoch)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(
+            DelegateSmtStorage::get_sub_leaves(&smt_manager, epoch, staker)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+    assert_eq!(
+        StakeSmtStorage::get_amount(&smt_manager, 3, staker)
+            .await
+            .unwrap(),
+        Some(30)
+    );
+    assert_eq!(
+        DelegateSmtStorage::get_amount(&smt_manager, 3, staker, delegator)
+            .await
+            .unwrap(),
+        Some(3)
+    );
+
+    // A pruned epoch can't be rolled back to.
+    assert_eq!(smt_manager.pruned_before().unwrap(), Some(3));
+    for err in [
+        StakeSmtStorage::rollback_to_epoch(&smt_manager, 2)
+            .await
+            .unwrap_err(),
+        DelegateSmtStorage::rollback_to_epoch(&smt_manager, 2)
+            .await
+            .unwrap_err(),
+    ] {
+        assert!(matches!(
+            err.downcast_ref::<StorageError>(),
+            Some(StorageError::Kvdb(KvdbError::EpochPruned(2, 3)))
+        ));
+    }
+
+    // Nor copied into the next epoch.
+    for err in [
+        StakeSmtStorage::new_epoch(&smt_manager, 3)
+            .await
+            .unwrap_err(),
+        DelegateSmtStorage::new_epoch(&smt_manager, 3)
+            .await
+            .unwrap_err(),
+    ] {
+        assert!(matches!(
+            err.downcast_ref::<StorageError>(),
+            Some(StorageError::Kvdb(KvdbError::EpochPruned(2, 3)))
+        ));
+    }
+
+    // The kept epoch is still copied into the next one.
+    StakeSmtStorage::new_epoch(&smt_manager, 4).await.unwrap();
+    assert_eq!(
+        StakeSmtStorage::get_amount(&smt_manager, 4, staker)
+            .await
+            .unwrap(),
+        Some(30)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_top_proof() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("top_proof");
+    let smt_manager = SmtManager::new(path);
+    let staker = [5u8; 20].into();
+
+    for epoch in 1..=3 {
+        StakeSmtStorage::insert(&smt_manager, epoch, vec![UserAmount {
+            user:        staker,
+            amount:      epoch as u128 * 10,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+    }
+
+    let root = StakeSmtStorage::get_top_root(&smt_manager).await.unwrap();
+    let proof = StakeSmtStorage::generate_top_proof(&smt_manager, vec![1, 3])
+        .await
+        .unwrap();
+    let mut leaves = vec![];
+    for epoch in [1, 3] {
+        let sub_root = StakeSmtStorage::get_sub_root(&smt_manager, epoch)
+            .await
+            .unwrap()
+            .unwrap();
+        leaves.push((epoch, sub_root));
+    }
+
+    assert!(SmtManager::verify_top_proof(root, leaves.clone(), proof.clone()).unwrap());
+
+    let (epoch, _) = leaves[1];
+    let wrong_root = StakeSmtStorage::get_sub_root(&smt_manager, 2)
+        .await
+        .unwrap()
+        .unwrap();
+    leaves[1] = (epoch, wrong_root);
+    assert!(!SmtManager::verify_top_proof(root, leaves, proof).unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_insert_and_read() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("concurrent");
+    let smt_manager = Arc::new(SmtManager::new(path));
+    let done = Arc::new(AtomicBool::new(false));
+    let stakers = (1..=20u8)
+        .map(|i| Staker::from([i; 20]))
+        .collect::<Vec<_>>();
+
+    let readers = (0..4)
+        .map(|_| {
+            let smt_manager = Arc::clone(&smt_manager);
+            let done = Arc::clone(&done);
+            tokio::spawn(async move {
+                while !done.load(Ordering::Acquire) {
+                    // Either no write yet or every staker of one write.
+                    let leaves = StakeSmtStorage::get_sub_leaves(&*smt_manager, 1)
+                        .await
+                        .unwrap();
+                    let amounts = leaves.values().collect::<HashSet<_>>();
+                    assert!(leaves.is_empty() || (leaves.len() == 20 && amounts.len() == 1));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for round in 1..=50u128 {
+        let amounts = stakers
+            .iter()
+            .map(|staker| UserAmount {
+                user:        *staker,
+                amount:      round,
+                is_increase: true,
+            })
+            .collect();
+        StakeSmtStorage::insert(&*smt_manager, 1, amounts)
+            .await
+            .unwrap();
+    }
+    done.store(true, Ordering::Release);
+    for reader in readers {
+        reader.await.unwrap();
+    }
+
+    assert_eq!(
+        StakeSmtStorage::get_amount(&*smt_manager, 1, stakers[0])
+            .await
+            .unwrap(),
+        Some(50)
+    );
+}
+
+#[tokio::test]
+async fn test_delegate_get_amounts() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("delegate_get_amounts");
+    let smt_manager = SmtManager::new(path);
+    let staker = [5u8; 20].into();
+    let delegators: [Delegator; 3] = [[6u8; 20].into(), [7u8; 20].into(), [8u8; 20].into()];
+
+    DelegateSmtStorage::insert(&smt_manager, 1, staker, vec![
+        UserAmount {
+            user:        delegators[0],
+            amount:      10,
+            is_increase: true,
+        },
+        UserAmount {
+            user:        delegators[2],
+            amount:      30,
+            is_increase: true,
+        },
+    ])
+    .await
+    .unwrap();
+
+    let query = [delegators[2], delegators[1], delegators[0]];
+    assert_eq!(
+        DelegateSmtStorage::get_amounts(&smt_manager, 1, staker, &query)
+            .await
+            .unwrap(),
+        vec![Some(30), None, Some(10)]
+    );
+    assert_eq!(
+        DelegateSmtStorage::get_amounts(&smt_manager, 2, staker, &query)
+            .await
+            .unwrap(),
+        vec![None, None, None]
+    );
+}
+
+#[tokio::test]
+async fn test_empty_sub_root() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("empty_sub_root");
+    let smt_manager = SmtManager::new(path);
+    let staker = [5u8; 20].into();
+
+    StakeSmtStorage::insert(&smt_manager, 1, vec![UserAmount {
+        user:        staker,
+        amount:      10,
+        is_increase: true,
+    }])
+    .await
+    .unwrap();
+
+    assert_ne!(
+        StakeSmtStorage::get_sub_root(&smt_manager, 1)
+            .await
+            .unwrap(),
+        Some(Root::zero())
+    );
+    assert_eq!(
+        StakeSmtStorage::get_sub_root(&smt_manager, 2)
+            .await
+            .unwrap(),
+        Some(Root::zero())
+    );
+    assert_eq!(
+        DelegateSmtStorage::get_sub_root(&smt_manager, 1, staker)
+            .await
+            .unwrap(),
+        Some(Root::zero())
+    );
+}
+
+#[tokio::test]
+async fn test_trees_isolated() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("isolated_shared");
+    let shared = SmtManager::new(path);
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("isolated_stake");
+    let stake_only = SmtManager::new(path);
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("isolated_proposal");
+    let proposal_only = SmtManager::new(path);
+    let address = [5u8; 20].into();
+    let stakers = vec![UserAmount {
+        user:        address,
+        amount:      10,
+        is_increase: true,
+    }];
+
+    // The same epoch and address in every tree of the shared database.
+    for smt_manager in [&shared, &stake_only] {
+        StakeSmtStorage::insert(smt_manager, 1, stakers.clone())
+            .await
+            .unwrap();
+    }
+    for smt_manager in [&shared, &proposal_only] {
+        ProposalSmtStorage::insert(smt_manager, 1, vec![(address, 7)])
+            .await
+            .unwrap();
+    }
+    RewardSmtStorage::insert(&shared, 1, address).await.unwrap();
+
+    assert_eq!(
+        StakeSmtStorage::get_top_root(&shared).await.unwrap(),
+        StakeSmtStorage::get_top_root(&stake_only).await.unwrap()
+    );
+    assert_eq!(
+        ProposalSmtStorage::get_top_root(&shared).await.unwrap(),
+        ProposalSmtStorage::get_top_root(&proposal_only)
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        StakeSmtStorage::get_sub_leaves(&shared, 1).await.unwrap(),
+        HashMap::from([(address, 10)])
+    );
+    assert_eq!(
+        ProposalSmtStorage::get_sub_leaves(&shared, 1)
+            .await
+            .unwrap(),
+        HashMap::from([(address, 7)])
+    );
+    assert!(DelegateSmtStorage::get_sub_leaves(&shared, 1, address)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_delegate_insert_many() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("delegate_insert_many");
+    let smt_manager = SmtManager::new(path);
+    let stakers: [Staker; 2] = [[5u8; 20].into(), [6u8; 20].into()];
+    let delegators: [Delegator; 2] = [[7u8; 20].into(), [8u8; 20].into()];
+    let amount = |user, amount| UserAmount {
+        user,
+        amount,
+        is_increase: true,
+    };
+
+    DelegateSmtStorage::insert(&smt_manager, 1, stakers[0], vec![amount(delegators[0], 10)])
+        .await
+        .unwrap();
+    DelegateSmtStorage::insert_many(
+        &smt_manager,
+        1,
+        HashMap::from([
+            (stakers[0], vec![amount(delegators[1], 20)]),
+            (stakers[1], vec![
+                amount(delegators[0], 30),
+                amount(delegators[1], 40),
+            ]),
+        ]),
+    )
+    .await
+    .unwrap();
+
+    let leaves = DelegateSmtStorage::get_sub_leaves(&smt_manager, 1, stakers[0])
+        .await
+        .unwrap();
+    assert_eq!(leaves, HashMap::from([(delegators[1], 20)]));
+    let leaves = DelegateSmtStorage::get_sub_leaves(&smt_manager, 1, stakers[1])
+        .await
+        .unwrap();
+    assert_eq!(
+        leaves,
+        HashMap::from([(delegators[0], 30), (delegators[1], 40)])
+    );
 }
 
 #[tokio::test]
@@ -165,3 +728,136 @@ async fn test_proposal_functions() {
         .unwrap();
     assert_eq!(result, proposal_count);
 }
+
+#[tokio::test]
+async fn test_new_epoch_replay() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("new_epoch_replay");
+    let smt_manager = SmtManager::new(path);
+    let staker_a = [5u8; 20].into();
+    let staker_b = [6u8; 20].into();
+    let delegator_a = [7u8; 20].into();
+    let delegator_b = [8u8; 20].into();
+    let epoch = 1;
+
+    let stakers = vec![UserAmount {
+        user:        staker_a,
+        amount:      100,
+        is_increase: true,
+    }];
+    let delegators = vec![UserAmount {
+        user:        delegator_a,
+        amount:      10,
+        is_increase: true,
+    }];
+    StakeSmtStorage::insert(&smt_manager, epoch, stakers.clone())
+        .await
+        .unwrap();
+    DelegateSmtStorage::insert(&smt_manager, epoch, staker_a, delegators)
+        .await
+        .unwrap();
+    StakeSmtStorage::new_epoch(&smt_manager, epoch + 1)
+        .await
+        .unwrap();
+    DelegateSmtStorage::new_epoch(&smt_manager, epoch + 1)
+        .await
+        .unwrap();
+
+    // the previous epoch changes before the transition is applied again
+    let mut stakers = stakers;
+    stakers.push(UserAmount {
+        user:        staker_b,
+        amount:      200,
+        is_increase: true,
+    });
+    let delegators = vec![UserAmount {
+        user:        delegator_b,
+        amount:      20,
+        is_increase: true,
+    }];
+    StakeSmtStorage::insert(&smt_manager, epoch, stakers)
+        .await
+        .unwrap();
+    DelegateSmtStorage::insert(&smt_manager, epoch, staker_a, delegators)
+        .await
+        .unwrap();
+    StakeSmtStorage::new_epoch(&smt_manager, epoch + 1)
+        .await
+        .unwrap();
+    DelegateSmtStorage::new_epoch(&smt_manager, epoch + 1)
+        .await
+        .unwrap();
+
+    let leaves = StakeSmtStorage::get_sub_leaves(&smt_manager, epoch + 1)
+        .await
+        .unwrap();
+    assert_eq!(leaves.len(), 2);
+    assert_eq!(leaves.get(&staker_a), Some(&100));
+    assert_eq!(leaves.get(&staker_b), Some(&200));
+
+    let result = DelegateSmtStorage::get_amount(&smt_manager, epoch + 1, staker_a, delegator_a)
+        .await
+        .unwrap();
+    assert_eq!(result, None);
+    let result = DelegateSmtStorage::get_amount(&smt_manager, epoch + 1, staker_a, delegator_b)
+        .await
+        .unwrap();
+    assert_eq!(result, Some(20));
+}
+
+#[tokio::test]
+async fn test_delegate_root_at_epoch() {
+    let mut path = PathBuf::from(ROCKSDB_PATH);
+    path.push("delegate_root_at_epoch");
+    let smt_manager = SmtManager::new(path);
+    let staker = [5u8; 20].into();
+    let delegator_a = [6u8; 20].into();
+    let delegator_b = [7u8; 20].into();
+
+    let delegators = vec![UserAmount {
+        user:        delegator_a,
+        amount:      100,
+        is_increase: true,
+    }];
+    DelegateSmtStorage::insert(&smt_manager, 1, staker, delegators)
+        .await
+        .unwrap();
+    let root_1 = DelegateSmtStorage::get_delegate_root_at_epoch(&smt_manager, staker, 1)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        Some(root_1),
+        DelegateSmtStorage::get_sub_root(&smt_manager, 1, staker)
+            .await
+            .unwrap()
+    );
+
+    let delegators = vec![UserAmount {
+        user:        delegator_b,
+        amount:      200,
+        is_increase: true,
+    }];
+    DelegateSmtStorage::insert(&smt_manager, 2, staker, delegators)
+        .await
+        .unwrap();
+    let root_2 = DelegateSmtStorage::get_delegate_root_at_epoch(&smt_manager, staker, 2)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The earlier root stays the same after the later epoch is written.
+    assert_ne!(root_1, root_2);
+    assert_eq!(
+        DelegateSmtStorage::get_delegate_root_at_epoch(&smt_manager, staker, 1)
+            .await
+            .unwrap(),
+        Some(root_1)
+    );
+    assert_eq!(
+        DelegateSmtStorage::get_delegate_root_at_epoch(&smt_manager, staker, 3)
+            .await
+            .unwrap(),
+        None
+    );
+}
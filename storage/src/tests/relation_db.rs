@@ -0,0 +1,621 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use common::{
+    traits::query::TransactionStorage,
+    types::{
+        api::{OperationType, SortOrder},
+        relation_db::transaction,
+        H160,
+    },
+    utils::convert::to_hex_address,
+};
+
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, Database, EntityTrait, QueryOrder, TransactionTrait};
+
+use crate::error::StorageError;
+use crate::relation_db::{
+    check_migrations, establish_connection, establish_connection_without_migration, ConnectOptions,
+    RetentionPolicy, Set, TransactionHistory,
+};
+
+static RELATION_DB_URL: &str = "sqlite::memory:";
+// Every mock record is a cell of its own, the records sharing a tx_hash are
+// all kept.
+static OUTPUT_INDEX: AtomicU32 = AtomicU32::new(0);
+
+fn mock_record(timestamp: u32) -> transaction::ActiveModel {
+    transaction::ActiveModel {
+        address: Set(to_hex_address(&H160::zero())),
+        timestamp: Set(timestamp),
+        operation: Set(0),
+        event: Set(0),
+        tx_hash: Set(format!("0x{:064x}", timestamp)),
+        total_amount: Set(100),
+        status: Set(0),
+        epoch: Set(1),
+        stake_amount: Set(100),
+        delegate_amount: Set(0),
+        withdrawable_amount: Set(0),
+        stake_rate: Set("".to_string()),
+        delegate_rate: Set("".to_string()),
+        output_index: Set(OUTPUT_INDEX.fetch_add(1, Ordering::Relaxed)),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_latest_stake_transactions_pagination() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    for timestamp in [1, 2, 2, 2, 3, 3, 4] {
+        relation_db.insert(mock_record(timestamp)).await.unwrap();
+    }
+
+    let mut records = vec![];
+    for page in 0..4 {
+        let res = relation_db
+            .get_latest_stake_transactions(page * 2, 2)
+            .await
+            .unwrap();
+        assert!(res.len() <= 2);
+        records.extend(res);
+    }
+
+    assert_eq!(records.len(), 7);
+    let ids = records.iter().map(|r| r.id).collect::<HashSet<_>>();
+    assert_eq!(ids.len(), 7);
+    assert!(records
+        .windows(2)
+        .all(|w| (w[0].timestamp, w[0].id) > (w[1].timestamp, w[1].id)));
+}
+
+fn mock_state_record(
+    address: H160,
+    operation: u32,
+    epoch: u32,
+    timestamp: u32,
+    amount: u32,
+) -> transaction::ActiveModel {
+    let mut record = mock_record(timestamp);
+    record.address = Set(to_hex_address(&address));
+    record.operation = Set(operation);
+    record.epoch = Set(epoch);
+    record.total_amount = Set(amount);
+    record.stake_amount = Set(amount);
+    record
+}
+
+async fn address_total(relation_db: &TransactionHistory, address: H160) -> (usize, u32) {
+    let records = relation_db.get_address_state(address).await.unwrap();
+    (
+        records.len(),
+        records.iter().map(|r| r.total_amount).sum::<u32>(),
+    )
+}
+
+#[tokio::test]
+async fn test_prune_transaction_history() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr_a = H160::from_low_u64_be(1);
+    let addr_b = H160::from_low_u64_be(2);
+
+    for (epoch, amount) in [(1, 10), (2, 20), (3, 30), (9, 40), (10, 50)] {
+        relation_db
+            .insert(mock_state_record(addr_a, 0, epoch, epoch * 100, amount))
+            .await
+            .unwrap();
+    }
+    for (epoch, amount) in [(2, 5), (10, 15)] {
+        relation_db
+            .insert(mock_state_record(addr_b, 0, epoch, epoch * 100, amount))
+            .await
+            .unwrap();
+    }
+    relation_db
+        .insert(mock_state_record(addr_a, 1, 2, 200, 7))
+        .await
+        .unwrap();
+
+    // Disabled by default.
+    let removed = relation_db
+        .prune(&RetentionPolicy::default(), 1000)
+        .await
+        .unwrap();
+    assert_eq!(removed, 0);
+
+    let policy = RetentionPolicy {
+        keep_epochs: Some(5),
+        keep_days:   None,
+    };
+    let removed = relation_db.prune(&policy, 1000).await.unwrap();
+    assert_eq!(removed, 5);
+
+    // Only the recent rows remain, untouched.
+    let records = relation_db.get_address_state(addr_a).await.unwrap();
+    let rows = records
+        .iter()
+        .map(|r| (r.epoch, r.total_amount))
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![(9, 40), (10, 50)]);
+    assert_eq!(address_total(&relation_db, addr_b).await, (1, 15));
+
+    // A window of days prunes by the timestamps.
+    let policy_days = RetentionPolicy {
+        keep_epochs: None,
+        keep_days:   Some(1),
+    };
+    let now = 900 + 24 * 60 * 60;
+    assert_eq!(relation_db.prune(&policy_days, now).await.unwrap(), 0);
+    assert_eq!(relation_db.prune(&policy_days, now + 1).await.unwrap(), 1);
+    assert_eq!(address_total(&relation_db, addr_a).await, (1, 50));
+    assert_eq!(address_total(&relation_db, addr_b).await, (1, 15));
+
+    // Pruning again is a no-op.
+    assert_eq!(relation_db.prune(&policy, 1000).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_prune_history_before() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+
+    for (epoch, amount) in [(1, 10), (2, 20), (3, 30), (4, 40)] {
+        relation_db
+            .insert(mock_state_record(addr, 0, epoch, epoch * 100, amount))
+            .await
+            .unwrap();
+    }
+
+    let removed = relation_db.prune_history_before(3).await.unwrap();
+    assert_eq!(removed, 2);
+
+    // No row below the cutoff remains.
+    let records = relation_db.get_address_state(addr).await.unwrap();
+    assert!(records.iter().all(|r| r.epoch >= 3));
+    assert_eq!(address_total(&relation_db, addr).await, (2, 30 + 40));
+
+    assert_eq!(relation_db.prune_history_before(3).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_ping() {
+    let relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    relation_db.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ping_timeout() {
+    let mut options = ConnectOptions::new(RELATION_DB_URL.to_owned());
+    options.max_connections(1);
+    let relation_db = TransactionHistory::new_with_options(options).await;
+
+    // The transaction holds the only connection of the pool.
+    let txn = relation_db.db.begin().await.unwrap();
+    let err = relation_db.ping().await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<StorageError>(),
+        Some(StorageError::PingTimeout(_))
+    ));
+
+    txn.rollback().await.unwrap();
+    relation_db.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_check_migrations() {
+    let db = establish_connection(RELATION_DB_URL).await.unwrap();
+    assert!(check_migrations(&db).await.is_ok());
+
+    let db = Database::connect(RELATION_DB_URL).await.unwrap();
+    assert!(check_migrations(&db).await.is_err());
+    assert!(establish_connection_without_migration(RELATION_DB_URL)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_inauguration_epoch_migration() {
+    let db = Database::connect(RELATION_DB_URL).await.unwrap();
+    // The schema before the inauguration epoch, with a stake, a delegate and a
+    // reward record.
+    Migrator::up(&db, Some(3)).await.unwrap();
+    for (operation, tx_hash) in [(0, "0x01"), (1, "0x02"), (2, "0x03")] {
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO "transaction" ("address", "timestamp", "operation", "event",
+                "tx_hash", "total_amount", "stake_amount", "delegate_amount",
+                "withdrawable_amount", "stake_rate", "delegate_rate", "epoch", "status")
+            VALUES ('', 1, {}, 0, '{}', 10, 10, 0, 0, '', '', 5, 0)"#,
+            operation, tx_hash
+        ))
+        .await
+        .unwrap();
+    }
+    Migrator::up(&db, None).await.unwrap();
+
+    let mut relation_db = TransactionHistory { db };
+    let mut record = mock_record(2);
+    record.operation = Set(OperationType::Stake as u32);
+    relation_db.insert(record).await.unwrap();
+
+    let records = transaction::Entity::find()
+        .order_by_asc(transaction::Column::Id)
+        .all(&relation_db.db)
+        .await
+        .unwrap();
+    let epochs = records
+        .iter()
+        .map(|r| r.inauguration_epoch)
+        .collect::<Vec<_>>();
+    assert_eq!(epochs, vec![None, None, None, None]);
+}
+
+#[tokio::test]
+async fn test_operation_history_cursor() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+    for epoch in 1..=3 {
+        relation_db
+            .insert(mock_state_record(addr, 0, epoch, epoch, 10))
+            .await
+            .unwrap();
+    }
+
+    let mut records = vec![];
+    let mut after = None;
+    for round in 0..4 {
+        let page = relation_db
+            .get_operation_history_after(addr, 0, SortOrder::Asc, after, 2)
+            .await
+            .unwrap();
+        if page.is_empty() {
+            break;
+        }
+        after = page.last().map(|r| r.id);
+        records.extend(page);
+
+        // New records arrive in the middle of the pagination.
+        if round == 0 {
+            for epoch in 4..=5 {
+                relation_db
+                    .insert(mock_state_record(addr, 0, epoch, epoch, 10))
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
+    let epochs = records.iter().map(|r| r.epoch).collect::<Vec<_>>();
+    assert_eq!(epochs, vec![1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn test_operation_history_pagination() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+    let other = H160::from_low_u64_be(2);
+    // Interleave other rows, so that the ids of the reward rows are not contiguous.
+    for epoch in 1..=5 {
+        relation_db
+            .insert(mock_state_record(addr, 2, epoch, epoch, 10))
+            .await
+            .unwrap();
+        relation_db
+            .insert(mock_state_record(other, 2, epoch, epoch, 10))
+            .await
+            .unwrap();
+        relation_db
+            .insert(mock_state_record(addr, 0, epoch, epoch, 10))
+            .await
+            .unwrap();
+    }
+
+    let mut epochs = vec![];
+    for (page, size) in [(0, 2), (1, 2), (2, 1), (3, 0)] {
+        let res = relation_db
+            .get_operation_history(addr, 2, SortOrder::Asc, page * 2, 2)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), size);
+        epochs.extend(res.iter().map(|r| r.epoch));
+    }
+    assert_eq!(epochs, vec![1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn test_stake_amount_by_epoch_pagination() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+    // Interleave other operations, so that the ids of the stake rows are not
+    // contiguous.
+    for epoch in 1..=5 {
+        relation_db
+            .insert(mock_state_record(addr, 0, epoch, epoch, 10))
+            .await
+            .unwrap();
+        relation_db
+            .insert(mock_state_record(addr, 1, epoch, epoch, 10))
+            .await
+            .unwrap();
+    }
+
+    let mut epochs = vec![];
+    for (page, size) in [(0, 2), (1, 2), (2, 1), (3, 0)] {
+        let res = relation_db
+            .get_stake_amount_by_epoch(0, page * 2, 2)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), size);
+        epochs.extend(res.iter().map(|r| r.epoch));
+    }
+    assert_eq!(epochs, vec![1, 2, 3, 4, 5]);
+}
+
+#[tokio::test]
+async fn test_history_by_epoch() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    for i in 1..=4 {
+        let addr = H160::from_low_u64_be(i);
+        for epoch in 1..=3 {
+            relation_db
+                .insert(mock_state_record(addr, (i % 2) as u32, epoch, epoch, 10))
+                .await
+                .unwrap();
+        }
+    }
+
+    let mut records = vec![];
+    for (page, size) in [(0, 3), (1, 1), (2, 0)] {
+        let res = relation_db
+            .get_history_by_epoch(2, None, page * 3, 3)
+            .await
+            .unwrap();
+        assert_eq!(res.len(), size);
+        records.extend(res);
+    }
+    assert!(records.iter().all(|r| r.epoch == 2));
+    let addresses = records
+        .iter()
+        .map(|r| r.address.clone())
+        .collect::<HashSet<_>>();
+    assert_eq!(addresses.len(), 4);
+
+    let res = relation_db
+        .get_history_by_epoch(2, Some(1), 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(res.len(), 2);
+    assert!(res.iter().all(|r| r.epoch == 2 && r.operation == 1));
+}
+
+#[tokio::test]
+async fn test_connect_options() {
+    let mut options = ConnectOptions::new(RELATION_DB_URL.to_owned());
+    options.max_connections(1).min_connections(1);
+    let mut relation_db = TransactionHistory::new_with_options(options).await;
+    relation_db.insert(mock_record(1)).await.unwrap();
+
+    let stats = relation_db.pool_stats().unwrap();
+    assert_eq!(stats.size, 1);
+    assert!(stats.idle <= 1);
+}
+
+#[tokio::test]
+async fn test_records_by_epoch_range() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+    let other = H160::from_low_u64_be(2);
+    for epoch in 1..=6 {
+        // Two records in each epoch, and records of another address and operation.
+        for timestamp in [epoch * 10, epoch * 10 + 1] {
+            relation_db
+                .insert(mock_state_record(addr, 0, epoch, timestamp, 10))
+                .await
+                .unwrap();
+        }
+        relation_db
+            .insert(mock_state_record(other, 0, epoch, epoch * 10, 10))
+            .await
+            .unwrap();
+        relation_db
+            .insert(mock_state_record(addr, 1, epoch, epoch * 10, 10))
+            .await
+            .unwrap();
+    }
+
+    let empty = relation_db
+        .get_records_by_epoch_range(addr, 0, 7, 9, 0, 10)
+        .await
+        .unwrap();
+    assert!(empty.is_empty());
+    let reversed = relation_db
+        .get_records_by_epoch_range(addr, 0, 4, 3, 0, 10)
+        .await
+        .unwrap();
+    assert!(reversed.is_empty());
+
+    // The range straddles the boundary between epoch 3 and 4, both ends are
+    // inclusive.
+    let mut records = vec![];
+    for page in 0..3 {
+        let res = relation_db
+            .get_records_by_epoch_range(addr, 0, 3, 4, page * 3, 3)
+            .await
+            .unwrap();
+        records.extend(res);
+    }
+    let epochs = records.iter().map(|r| r.epoch).collect::<Vec<_>>();
+    assert_eq!(epochs, vec![3, 3, 4, 4]);
+    assert!(records.iter().all(|r| r.operation == 0));
+}
+
+#[tokio::test]
+async fn test_operation_history_desc() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+    for epoch in 1..=5 {
+        relation_db
+            .insert(mock_state_record(addr, 0, epoch, epoch, 10))
+            .await
+            .unwrap();
+    }
+
+    let mut epochs = vec![];
+    for page in 0..3 {
+        let res = relation_db
+            .get_operation_history(addr, 0, SortOrder::Desc, page * 2, 2)
+            .await
+            .unwrap();
+        epochs.extend(res.iter().map(|r| r.epoch));
+    }
+    assert_eq!(epochs, vec![5, 4, 3, 2, 1]);
+
+    let mut records = vec![];
+    let mut after = None;
+    loop {
+        let page = relation_db
+            .get_operation_history_after(addr, 0, SortOrder::Desc, after, 2)
+            .await
+            .unwrap();
+        if page.is_empty() {
+            break;
+        }
+        after = page.last().map(|r| r.id);
+        records.extend(page);
+    }
+    let epochs = records.iter().map(|r| r.epoch).collect::<Vec<_>>();
+    assert_eq!(epochs, vec![5, 4, 3, 2, 1]);
+
+    let records = relation_db
+        .get_records_by_address(addr, SortOrder::Desc, 1, 2)
+        .await
+        .unwrap();
+    let epochs = records.iter().map(|r| r.epoch).collect::<Vec<_>>();
+    assert_eq!(epochs, vec![4, 3]);
+}
+
+#[tokio::test]
+async fn test_top_stake_address() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let system = H160::from_low_u64_be(9);
+    for (i, amount) in [(1, 0), (2, 10), (3, 0), (4, 20)] {
+        relation_db
+            .insert(mock_state_record(H160::from_low_u64_be(i), 0, 1, 1, amount))
+            .await
+            .unwrap();
+    }
+    relation_db
+        .insert(mock_state_record(system, 0, 1, 1, 30))
+        .await
+        .unwrap();
+
+    let records = relation_db
+        .get_top_stake_address(0, vec![], 0, 10)
+        .await
+        .unwrap();
+    let amounts = records.iter().map(|r| r.total_amount).collect::<Vec<_>>();
+    assert_eq!(amounts, vec![30, 20, 10]);
+
+    // The second page of two.
+    let records = relation_db
+        .get_top_stake_address(0, vec![], 2, 2)
+        .await
+        .unwrap();
+    let amounts = records.iter().map(|r| r.total_amount).collect::<Vec<_>>();
+    assert_eq!(amounts, vec![10]);
+
+    let records = relation_db
+        .get_top_stake_address(0, vec![system], 0, 10)
+        .await
+        .unwrap();
+    let addresses = records
+        .iter()
+        .map(|r| r.address.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(addresses.len(), 2);
+    assert!(!addresses.contains(&to_hex_address(&system)));
+}
+
+#[tokio::test]
+async fn test_insert_batch() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    relation_db.insert_batch(vec![]).await.unwrap();
+
+    let addr = H160::from_low_u64_be(1);
+    let records = (1..=3)
+        .map(|epoch| mock_state_record(addr, 0, epoch, epoch, 10))
+        .collect();
+    relation_db.insert_batch(records).await.unwrap();
+
+    let records = relation_db.get_address_state(addr).await.unwrap();
+    let epochs = records.iter().map(|r| r.epoch).collect::<Vec<_>>();
+    assert_eq!(epochs, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_insert_batch_over_many_statements() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+    let records = (1..=1201)
+        .map(|timestamp| mock_state_record(addr, 0, 1, timestamp, 1))
+        .collect();
+    relation_db.insert_batch(records).await.unwrap();
+
+    assert_eq!(address_total(&relation_db, addr).await, (1201, 1201));
+}
+
+#[tokio::test]
+async fn test_reprocess_multi_cell_transaction() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let addr = H160::from_low_u64_be(1);
+    let cells = || {
+        (0..2)
+            .map(|output_index| {
+                let mut record = mock_state_record(addr, 0, 1, 1, 10);
+                record.output_index = Set(output_index);
+                record
+            })
+            .collect::<Vec<_>>()
+    };
+
+    relation_db.insert_batch(cells()).await.unwrap();
+    relation_db.insert_batch(cells()).await.unwrap();
+    for record in cells() {
+        relation_db.insert(record).await.unwrap();
+    }
+
+    let records = relation_db.get_address_state(addr).await.unwrap();
+    let cells = records
+        .iter()
+        .map(|r| (r.tx_hash.as_str(), r.output_index))
+        .collect::<Vec<_>>();
+    let tx_hash = format!("0x{:064x}", 1);
+    assert_eq!(cells, vec![(tx_hash.as_str(), 0), (tx_hash.as_str(), 1)]);
+}
+
+#[tokio::test]
+async fn test_delegators_of_staker() {
+    let mut relation_db = TransactionHistory::new(RELATION_DB_URL).await;
+    let staker = H160::from_low_u64_be(1);
+    let other = H160::from_low_u64_be(2);
+    for (delegator, to) in [(3, staker), (4, staker), (5, other)] {
+        let mut record = mock_state_record(H160::from_low_u64_be(delegator), 1, 1, 1, 10);
+        record.staker = Set(to_hex_address(&to));
+        relation_db.insert(record).await.unwrap();
+    }
+    // The stake of the staker itself is not a delegation.
+    relation_db
+        .insert(mock_state_record(staker, 0, 1, 1, 10))
+        .await
+        .unwrap();
+
+    let records = relation_db.get_delegators_of_staker(staker).await.unwrap();
+    let delegators = records
+        .iter()
+        .map(|r| r.address.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(delegators, vec![
+        to_hex_address(&H160::from_low_u64_be(3)),
+        to_hex_address(&H160::from_low_u64_be(4))
+    ]);
+}
@@ -1,7 +1,8 @@
 use ethereum_types::H160;
 use thiserror::Error;
 
-use common::types::tx_builder::{Amount, Epoch};
+use common::types::tx_builder::{Amount, DelegateItemError, Epoch};
+use common::utils::convert::SplitError;
 
 pub type CkbTxResult<T> = std::result::Result<T, CkbTxErr>;
 
@@ -34,6 +35,13 @@ pub enum CkbTxErr {
         outputs_capacity: u64,
     },
 
+    #[error("Output {index:?} is below its occupied capacity: {capacity:?} < {occupied:?}")]
+    BelowOccupiedCapacity {
+        index:    usize,
+        capacity: u64,
+        occupied: u64,
+    },
+
     #[error(
         "The minted amount is too large, minted amount: {total_mint:?}, max supply: {max_supply:?}"
     )]
@@ -45,15 +53,36 @@ pub enum CkbTxErr {
     #[error("Cell not found: {0}")]
     CellNotFound(String),
 
+    #[error("Cell has no data: {0}")]
+    CellDataNotFound(String),
+
+    #[error("Nothing to withdraw at epoch {0}")]
+    NothingToWithdraw(Epoch),
+
     #[error("Deserialize bls pub key error")]
     Deserialize,
 
     #[error("User's reward epoch not found")]
     RewardEpochNotFound,
 
+    #[error("The rewards from epoch {start_epoch} are not unlocked, unlocked up to epoch {unlocked_epoch}")]
+    RewardNotUnlocked {
+        start_epoch:    Epoch,
+        unlocked_epoch: Epoch,
+    },
+
+    #[error("Nothing to claim from epoch {start_epoch} to {end_epoch}")]
+    NothingToClaim {
+        start_epoch: Epoch,
+        end_epoch:   Epoch,
+    },
+
     #[error("The minimum value of the current epoch should be 2")]
     EpochTooSmall,
 
+    #[error("The epoch count of a reward claim should be at least 1")]
+    ZeroEpochCount,
+
     #[error("Stake amount not found in stack SMT")]
     StakeAmountNotFound(H160),
 
@@ -67,6 +96,18 @@ pub enum CkbTxErr {
         recorded_period: u32,
     },
 
+    #[error("The metadata cell has no metadata")]
+    EmptyMetadata,
+
     #[error("there should be only one smt cell for the tx, found: {0}")]
     SmtCellNum(usize),
+
+    #[error("Invalid AT cell data: {0}")]
+    CellData(#[from] SplitError),
+
+    #[error("Invalid delegate item {index}: {source}")]
+    DelegateItem {
+        index:  usize,
+        source: DelegateItemError,
+    },
 }
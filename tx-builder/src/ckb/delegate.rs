@@ -65,15 +65,7 @@ impl<'a, C: CkbRpc> IDelegateTxBuilder<'a, C> for DelegateTxBuilder<'a, C> {
     }
 
     async fn build_tx(&self) -> Result<TransactionView> {
-        for delegate in self.delegators.iter() {
-            if delegate.inauguration_epoch > self.current_epoch + INAUGURATION {
-                return Err(CkbTxErr::InaugurationEpoch {
-                    expected: self.current_epoch,
-                    found:    delegate.inauguration_epoch,
-                }
-                .into());
-            }
-        }
+        self.check_delegators()?;
 
         let delegate_cell =
             Delegate::get_cell(self.ckb, self.delegate_lock.clone(), self.xudt.clone()).await?;
@@ -88,6 +80,22 @@ impl<'a, C: CkbRpc> IDelegateTxBuilder<'a, C> for DelegateTxBuilder<'a, C> {
 }
 
 impl<'a, C: CkbRpc> DelegateTxBuilder<'a, C> {
+    pub(crate) fn check_delegators(&self) -> CkbTxResult<()> {
+        for (index, delegate) in self.delegators.iter().enumerate() {
+            delegate
+                .validate()
+                .map_err(|source| CkbTxErr::DelegateItem { index, source })?;
+
+            if delegate.inauguration_epoch > self.current_epoch + INAUGURATION {
+                return Err(CkbTxErr::InaugurationEpoch {
+                    expected: self.current_epoch,
+                    found:    delegate.inauguration_epoch,
+                });
+            }
+        }
+        Ok(())
+    }
+
     async fn build_first_delegate_tx(&self) -> Result<TransactionView> {
         let mut inputs = vec![];
 
@@ -295,10 +303,9 @@ impl<'a, C: CkbRpc> DelegateTxBuilder<'a, C> {
         mut wallet_amount: Amount,
         delegate_data: Bytes,
     ) -> CkbTxResult<Vec<Bytes>> {
-        let mut total_delegate_amount = new_u128(&delegate_data[..TOKEN_BYTES]);
-
-        let mut delegate_data = delegate_data;
-        let delegate_data = DelegateAtCellData::new_unchecked(delegate_data.split_off(TOKEN_BYTES));
+        let (total_delegate_amount, delegate_data) = safe_split(delegate_data, TOKEN_BYTES)?;
+        let mut total_delegate_amount = new_u128(&total_delegate_amount);
+        let delegate_data = DelegateAtCellData::new_unchecked(delegate_data);
 
         let (updated_delegates, new_stakers) = self.process_new_delegates(
             &delegate_data.lock(),
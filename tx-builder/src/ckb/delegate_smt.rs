@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -16,7 +17,7 @@ use common::traits::{
 };
 use common::types::axon_types::delegate::{
     DelegateArgs, DelegateAtCellData, DelegateAtCellLockData as ADelegateAtCellLockData,
-    DelegateCellData, DelegateInfoDeltas, DelegateSmtCellData as ADelegateSmtCellData,
+    DelegateInfoDeltas, DelegateSmtCellData as ADelegateSmtCellData,
 };
 use common::types::ckb_rpc_client::Cell;
 use common::types::smt::{Delegator as SmtDelegator, UserAmount};
@@ -24,7 +25,7 @@ use common::types::tx_builder::{
     Amount, DelegateItem, DelegateSmtTypeIds, Delegator, Epoch, InDelegateSmt, InStakeSmt,
     NonTopDelegators, PrivateKey, Staker as TxStaker,
 };
-use common::utils::convert::{new_u128, to_ckb_h160, to_eth_h160, to_uint128, to_usize};
+use common::utils::convert::{new_u128, safe_split, to_ckb_h160, to_eth_h160, to_uint128};
 
 use crate::ckb::define::types::{DelegateInfo, StakeGroupInfo};
 use crate::ckb::define::{
@@ -33,13 +34,13 @@ use crate::ckb::define::{
     types::{DelegateAtCellLockData, DelegateSmtCellData, StakerSmtRoot},
 };
 use crate::ckb::helper::{
-    token_cell_data, AlwaysSuccess, Checkpoint, Delegate, Metadata, OmniEth, Secp256k1, Tx,
-    Withdraw, Xudt,
+    token_cell_data, AlwaysSuccess, Checkpoint, Delegate, Metadata, OmniEth, PrivateKeySigner,
+    Secp256k1, Signer, Tx, Withdraw, Xudt,
 };
 
 pub struct DelegateSmtTxBuilder<'a, C: CkbRpc, D: DelegateSmtStorage> {
     ckb:                   &'a C,
-    kicker:                PrivateKey,
+    signer:                Arc<dyn Signer>,
     current_epoch:         Epoch,
     type_ids:              DelegateSmtTypeIds,
     delegate_cells:        Vec<Cell>,
@@ -61,7 +62,7 @@ impl<'a, C: CkbRpc, D: DelegateSmtStorage> IDelegateSmtTxBuilder<'a, C, D>
     ) -> Self {
         Self {
             ckb,
-            kicker,
+            signer: Arc::new(PrivateKeySigner::new(kicker)),
             current_epoch,
             type_ids,
             delegate_cells,
@@ -127,18 +128,20 @@ impl<'a, C: CkbRpc, D: DelegateSmtStorage> IDelegateSmtTxBuilder<'a, C, D>
             .witnesses(witnesses.pack())
             .build();
 
-        let omni_eth = OmniEth::new(self.kicker.clone());
-        let kicker_lock = OmniEth::lock(&omni_eth.address()?);
+        let kicker_lock = OmniEth::lock(&self.signer.address()?);
 
         let mut tx = Tx::new(self.ckb, tx);
         tx.balance(kicker_lock.clone()).await?;
 
-        tx.sign(&omni_eth.signer()?, &ScriptGroup {
-            script:         kicker_lock,
-            group_type:     ScriptGroupType::Lock,
-            input_indices:  vec![tx.inner_ref().inputs().len() - 1],
-            output_indices: vec![],
-        })?;
+        tx.sign(
+            &OmniEth::external_signer(Arc::clone(&self.signer))?,
+            &ScriptGroup {
+                script:         kicker_lock,
+                group_type:     ScriptGroupType::Lock,
+                input_indices:  vec![tx.inner_ref().inputs().len() - 1],
+                output_indices: vec![],
+            },
+        )?;
 
         Ok((tx.inner(), statistics.non_top_delegators))
     }
@@ -150,6 +153,12 @@ struct Statistics {
 }
 
 impl<'a, C: CkbRpc, D: DelegateSmtStorage> DelegateSmtTxBuilder<'a, C, D> {
+    /// Replace the in-process kicker key signer, e.g. with a remote signer.
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = signer;
+        self
+    }
+
     async fn fill_tx(
         &self,
         statistics: &Statistics,
@@ -170,7 +179,7 @@ impl<'a, C: CkbRpc, D: DelegateSmtStorage> DelegateSmtTxBuilder<'a, C, D> {
             witnesses.push(Delegate::witness(1).as_bytes());
 
             let (old_total_delegate_amount, old_delegate_data) =
-                self.parse_delegate_data(delegate_cell);
+                self.parse_delegate_data(delegate_cell)?;
 
             let withdraw_lock = Withdraw::lock(&self.type_ids.metadata_type_id, delegator);
 
@@ -237,7 +246,7 @@ impl<'a, C: CkbRpc, D: DelegateSmtStorage> DelegateSmtTxBuilder<'a, C, D> {
                             old_withdraw_cell,
                             self.current_epoch + INAUGURATION,
                             total_withdraw_amount,
-                        )),
+                        )?),
                     )
                 } else {
                     let mut new_delegates = DelegateInfoDeltas::new_builder();
@@ -291,12 +300,17 @@ impl<'a, C: CkbRpc, D: DelegateSmtStorage> DelegateSmtTxBuilder<'a, C, D> {
         Ok(())
     }
 
-    fn parse_delegate_data(&self, cell: &Cell) -> (Amount, DelegateAtCellData) {
-        let mut cell_data_bytes = cell.output_data.clone().unwrap().into_bytes();
-        let total_delegate_amount = new_u128(&cell_data_bytes[..TOKEN_BYTES]);
-        let delegate_data =
-            DelegateAtCellData::new_unchecked(cell_data_bytes.split_off(TOKEN_BYTES));
-        (total_delegate_amount, delegate_data)
+    fn parse_delegate_data(&self, cell: &Cell) -> Result<(Amount, DelegateAtCellData)> {
+        let cell_data_bytes = cell
+            .output_data
+            .clone()
+            .ok_or_else(|| CkbTxErr::CellDataNotFound("delegate AT".to_owned()))?
+            .into_bytes();
+        let (total_delegate_amount, delegate_data) = safe_split(cell_data_bytes, TOKEN_BYTES)?;
+        Ok((
+            new_u128(&total_delegate_amount),
+            DelegateAtCellData::new_unchecked(delegate_data),
+        ))
     }
 
     async fn collect(&mut self) -> Result<(Bytes, Statistics, WitnessArgs)> {
@@ -412,9 +426,9 @@ impl<'a, C: CkbRpc, D: DelegateSmtStorage> DelegateSmtTxBuilder<'a, C, D> {
                     .as_bytes(),
             )?;
 
-            let mut cell_bytes = cell.output_data.clone().unwrap().into_bytes();
-
-            let delegate = &DelegateAtCellData::new_unchecked(cell_bytes.split_off(TOKEN_BYTES));
+            let cell_bytes = cell.output_data.clone().unwrap().into_bytes();
+            let (_, delegate_data) = safe_split(cell_bytes, TOKEN_BYTES)?;
+            let delegate = &DelegateAtCellData::new_unchecked(delegate_data);
             let delegate_infos = delegate.lock().delegator_infos();
             let mut expired = false;
 
@@ -541,20 +555,8 @@ impl<'a, C: CkbRpc, D: DelegateSmtStorage> DelegateSmtTxBuilder<'a, C, D> {
     }
 
     async fn get_maximum_delegators(&self, staker: &TxStaker) -> Result<usize> {
-        let delegate_requirement_cell = Delegate::get_requirement_cell(
-            self.ckb,
-            Delegate::requirement_type(&self.type_ids.metadata_type_id, staker),
-        )
-        .await?;
-
-        let delegate_requirement_cell_bytes =
-            delegate_requirement_cell.output_data.unwrap().into_bytes();
-        let delegate_cell_info = DelegateCellData::new_unchecked(delegate_requirement_cell_bytes);
-        let maximum_delegators = to_usize(
-            delegate_cell_info
-                .delegate_requirement()
-                .max_delegator_size(),
-        );
-        Ok(maximum_delegators)
+        let maximum_delegators =
+            Delegate::maximum_delegators(self.ckb, &self.type_ids.metadata_type_id, staker).await?;
+        Ok(maximum_delegators as usize)
     }
 }
@@ -6,10 +6,11 @@ use ckb_types::H256;
 
 use common::traits::ckb_rpc_client::CkbRpc;
 use common::types::ckb_rpc_client::Cell;
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{NetworkType, ScriptKind};
 
 use crate::ckb::define::scripts::*;
 use crate::ckb::helper::ckb::cell_collector::get_cell_by_type;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::helper::unique_cell_dep;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
@@ -34,7 +35,7 @@ impl Checkpoint {
     }
 
     pub fn type_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &CHECKPOINT_TYPE_MAINNET.tx_hash,
                 CHECKPOINT_TYPE_MAINNET.index,
@@ -45,7 +46,8 @@ impl Checkpoint {
                 CHECKPOINT_TYPE_TESTNET.index,
                 CHECKPOINT_TYPE_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::Checkpoint, cell_dep)
     }
 
     pub async fn cell_dep(ckb_rpc: &impl CkbRpc, type_id: &H256) -> Result<CellDep> {
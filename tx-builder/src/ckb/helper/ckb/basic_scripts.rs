@@ -6,9 +6,10 @@ use ckb_types::packed::{CellDep, CellInput, OutPoint, Script};
 use ckb_types::prelude::{Builder, Entity, Pack};
 use ckb_types::H256;
 
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{NetworkType, ScriptKind};
 
 use crate::ckb::define::scripts::*;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
 
@@ -33,7 +34,7 @@ impl AlwaysSuccess {
     }
 
     pub fn lock_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &ALWAYS_SUCCESS_LOCK_MAINNET.tx_hash,
                 ALWAYS_SUCCESS_LOCK_MAINNET.index,
@@ -44,13 +45,14 @@ impl AlwaysSuccess {
                 ALWAYS_SUCCESS_LOCK_TESTNET.index,
                 ALWAYS_SUCCESS_LOCK_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::AlwaysSuccess, cell_dep)
     }
 }
 
 impl Secp256k1 {
     pub fn lock_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &SECP2561_BLAKE160_MAINNET.tx_hash,
                 SECP2561_BLAKE160_MAINNET.index,
@@ -61,7 +63,8 @@ impl Secp256k1 {
                 SECP2561_BLAKE160_TESTNET.index,
                 SECP2561_BLAKE160_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::Secp256k1, cell_dep)
     }
 }
 
@@ -1,10 +1,12 @@
 pub mod basic_scripts;
 pub mod cell_collector;
 pub mod omni;
+pub mod signer;
 pub mod tx;
 pub mod xudt;
 
 pub use basic_scripts::{AlwaysSuccess, Secp256k1, TypeId};
 pub use omni::OmniEth;
+pub use signer::{PrivateKeySigner, Signer};
 pub use tx::Tx;
 pub use xudt::Xudt;
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use ckb_crypto::secp::Pubkey;
 use ckb_sdk::traits::SecpCkbRawKeySigner;
@@ -11,9 +13,11 @@ use ckb_types::prelude::{Builder, Pack};
 use ckb_types::{H160, H256};
 use molecule::prelude::Entity;
 
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{NetworkType, ScriptKind};
 
 use crate::ckb::define::scripts::{OMNI_LOCK_MAINNET, OMNI_LOCK_TESTNET};
+use crate::ckb::helper::ckb::signer::{Signer, SignerAdapter};
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
 
@@ -48,27 +52,33 @@ impl OmniEth {
         ))
     }
 
+    pub fn external_signer(signer: Arc<dyn Signer>) -> Result<OmniLockScriptSigner> {
+        let config = OmniLockConfig::new_ethereum(signer.address()?);
+        Ok(OmniLockScriptSigner::new(
+            Box::new(SignerAdapter::new(signer)),
+            config,
+            OmniUnlockMode::Normal,
+        ))
+    }
+
     pub fn ckb_address(&self) -> Result<String> {
-        let addr = self.address()?;
-        let config = OmniLockConfig::new_ethereum(addr);
-        Ok(match **NETWORK_TYPE.load() {
-            NetworkType::Mainnet => {
-                let address_payload = ckb_sdk::AddressPayload::new_full(
-                    ScriptHashType::Type,
-                    OMNI_LOCK_MAINNET.code_hash.clone().pack(),
-                    config.build_args(),
-                );
-                Address::new(ckb_sdk::NetworkType::Mainnet, address_payload, true).to_string()
-            }
-            NetworkType::Testnet => {
-                let address_payload = ckb_sdk::AddressPayload::new_full(
-                    ScriptHashType::Type,
-                    OMNI_LOCK_TESTNET.code_hash.clone().pack(),
-                    config.build_args(),
-                );
-                Address::new(ckb_sdk::NetworkType::Testnet, address_payload, true).to_string()
-            }
-        })
+        Ok(Self::address_of(&self.address()?, &NETWORK_TYPE.load()))
+    }
+
+    /// The full format CKB address of the omni lock of the Ethereum address,
+    /// `ckb` prefixed on mainnet and `ckt` on testnet.
+    pub fn address_of(eth_addr: &H160, network_type: &NetworkType) -> String {
+        let config = OmniLockConfig::new_ethereum(eth_addr.clone());
+        let (code_hash, network) = match network_type {
+            NetworkType::Mainnet => (&OMNI_LOCK_MAINNET.code_hash, ckb_sdk::NetworkType::Mainnet),
+            NetworkType::Testnet => (&OMNI_LOCK_TESTNET.code_hash, ckb_sdk::NetworkType::Testnet),
+        };
+        let address_payload = ckb_sdk::AddressPayload::new_full(
+            ScriptHashType::Type,
+            code_hash.clone().pack(),
+            config.build_args(),
+        );
+        Address::new(network, address_payload, true).to_string()
     }
 
     pub fn witness_placeholder() -> WitnessArgs {
@@ -106,7 +116,7 @@ impl OmniEth {
     }
 
     pub fn lock_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &OMNI_LOCK_MAINNET.tx_hash,
                 OMNI_LOCK_MAINNET.index,
@@ -117,6 +127,7 @@ impl OmniEth {
                 OMNI_LOCK_TESTNET.index,
                 OMNI_LOCK_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::OmniLock, cell_dep)
     }
 }
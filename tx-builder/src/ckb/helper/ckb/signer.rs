@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use ckb_sdk::traits::{Signer as CkbSigner, SignerError};
+use ckb_sdk::SECP256K1;
+use ckb_types::{bytes::Bytes, core::TransactionView, H160, H256};
+
+use common::types::tx_builder::PrivateKey;
+
+use crate::ckb::helper::ckb::omni::OmniEth;
+
+/// Signs the kicker's transactions. The default implementation keeps the
+/// private key in process, an alternate one can delegate to an HSM or a
+/// remote signing service.
+pub trait Signer: Send + Sync {
+    /// The Ethereum address of the signing key.
+    fn address(&self) -> Result<H160>;
+
+    /// Sign a message hash, returning a 65 bytes recoverable signature.
+    fn sign(&self, message: &H256) -> Result<Bytes>;
+}
+
+pub struct PrivateKeySigner {
+    private_key: PrivateKey,
+}
+
+impl PrivateKeySigner {
+    pub fn new(private_key: PrivateKey) -> Self {
+        Self { private_key }
+    }
+}
+
+impl Signer for PrivateKeySigner {
+    fn address(&self) -> Result<H160> {
+        OmniEth::new(self.private_key.clone()).address()
+    }
+
+    fn sign(&self, message: &H256) -> Result<Bytes> {
+        let key = secp256k1::SecretKey::from_slice(self.private_key.as_bytes())?;
+        let message = secp256k1::Message::from_slice(message.as_bytes())?;
+        let (rec_id, data) = SECP256K1
+            .sign_ecdsa_recoverable(&message, &key)
+            .serialize_compact();
+
+        let mut signature = [0u8; 65];
+        signature[..64].copy_from_slice(&data);
+        signature[64] = rec_id.to_i32() as u8;
+        Ok(Bytes::from(signature.to_vec()))
+    }
+}
+
+/// Adapts a [`Signer`] to the signer interface used by the omni lock script
+/// signer of ckb-sdk.
+pub struct SignerAdapter {
+    inner: Arc<dyn Signer>,
+}
+
+impl SignerAdapter {
+    pub fn new(inner: Arc<dyn Signer>) -> Self {
+        Self { inner }
+    }
+}
+
+impl CkbSigner for SignerAdapter {
+    fn match_id(&self, id: &[u8]) -> bool {
+        self.inner
+            .address()
+            .map(|addr| addr.as_bytes() == id)
+            .unwrap_or(false)
+    }
+
+    fn sign(
+        &self,
+        id: &[u8],
+        message: &[u8],
+        recoverable: bool,
+        _tx: &TransactionView,
+    ) -> Result<Bytes, SignerError> {
+        if !self.match_id(id) {
+            return Err(SignerError::IdNotFound);
+        }
+
+        if !recoverable {
+            return Err(SignerError::NotSupported(
+                "non-recoverable signature".to_owned(),
+            ));
+        }
+
+        let message = H256::from_slice(message).map_err(|_| {
+            SignerError::InvalidMessage(format!("expected length: 32, got: {}", message.len()))
+        })?;
+
+        self.inner.sign(&message).map_err(SignerError::Other)
+    }
+}
@@ -58,17 +58,25 @@ impl<'a, C: CkbRpc> Tx<'a, C> {
 
     /// There is no pure CKB cell in the input and output of the transaction.
     /// Collect CKB cells and add them to the input of the transaction.
-    /// Add a CKB change cell to the output of the transaction.
+    /// Add a CKB change cell to the output of the transaction, unless the
+    /// change is below the occupied capacity of the cell, then it goes to the
+    /// fee. So the fee exceeds the one of the fee rate by less than the
+    /// occupied capacity of a change cell of the lock, 63 CKB for an omni lock.
     pub async fn balance(&mut self, capacity_provider: Script) -> Result<()> {
-        let outputs_capacity = self.add_ckb_to_outputs(capacity_provider.clone())?;
+        self.check_occupied_capacity()?;
+
+        let outputs = self.tx.outputs().into_iter().collect::<Vec<_>>();
+        let outputs_capacity = Self::calc_outputs_capacity(&outputs);
+        let change_output = CellOutput::new_builder()
+            .lock(capacity_provider.clone())
+            .build_exact_capacity(Capacity::zero())?;
+        let change_occupied: u64 = change_output.capacity().unpack();
 
         let inputs_capacity = self
-            .add_ckb_to_intputs(capacity_provider.clone(), outputs_capacity)
+            .add_ckb_to_intputs(capacity_provider, outputs_capacity + change_occupied)
             .await?;
 
-        self.change_ckb(inputs_capacity, outputs_capacity)?;
-
-        Ok(())
+        self.change_ckb(change_output, inputs_capacity, outputs_capacity)
     }
 
     pub fn sign(&mut self, signer: &impl ScriptSigner, script_group: &ScriptGroup) -> Result<()> {
@@ -150,30 +158,6 @@ impl<'a, C: CkbRpc> Tx<'a, C> {
         })
     }
 
-    fn add_ckb_to_outputs(&mut self, capacity_provider: Script) -> Result<u64> {
-        let mut outputs = self.tx.outputs().into_iter().collect::<Vec<_>>();
-        let mut outputs_data = self.tx.outputs_data().into_iter().collect::<Vec<_>>();
-
-        outputs.push(
-            CellOutput::new_builder()
-                .lock(capacity_provider)
-                .build_exact_capacity(Capacity::zero())?,
-        );
-
-        outputs_data.push(Bytes::default());
-
-        let outputs_capacity = Self::calc_outputs_capacity(&outputs);
-
-        self.tx = self
-            .tx
-            .as_advanced_builder()
-            .set_outputs(outputs)
-            .set_outputs_data(outputs_data)
-            .build();
-
-        Ok(outputs_capacity)
-    }
-
     async fn add_ckb_to_intputs(
         &mut self,
         capacity_provider: Script,
@@ -203,10 +187,42 @@ impl<'a, C: CkbRpc> Tx<'a, C> {
         Ok(inputs_capacity)
     }
 
-    fn change_ckb(&mut self, inputs_capacity: u64, outputs_capacity: u64) -> Result<()> {
+    /// Pay the change to a cell of `change_output`, or burn it as fee, with a
+    /// warning, if it is less than the occupied capacity of the cell.
+    fn change_ckb(
+        &mut self,
+        change_output: CellOutput,
+        inputs_capacity: u64,
+        outputs_capacity: u64,
+    ) -> Result<()> {
+        let change_occupied: u64 = change_output.capacity().unpack();
+        // The capacity of the change cell doesn't change the size.
+        let tx_size = self
+            .tx
+            .as_advanced_builder()
+            .output(change_output.clone())
+            .output_data(Bytes::default())
+            .build()
+            .data()
+            .as_reader()
+            .serialized_size_in_block();
+        let fee = Self::fee(tx_size).as_u64();
+
+        if let Some(change) = inputs_capacity.checked_sub(outputs_capacity + fee) {
+            if change >= change_occupied {
+                self.tx = self
+                    .tx
+                    .as_advanced_builder()
+                    .output(change_output.as_builder().capacity(change.pack()).build())
+                    .output_data(Bytes::default())
+                    .build();
+                return Ok(());
+            }
+        }
+
+        // The change can't pay for a cell of its own, it goes to the fee.
         let tx_size = self.tx.data().as_reader().serialized_size_in_block();
         let needed_capacity = outputs_capacity + Self::fee(tx_size).as_u64();
-
         if inputs_capacity < needed_capacity {
             return Err(CkbTxErr::InsufficientCapacity {
                 inputs_capacity,
@@ -214,28 +230,36 @@ impl<'a, C: CkbRpc> Tx<'a, C> {
             }
             .into());
         }
+        log::warn!(
+            "The change of {} shannons is below the occupied capacity {} of a change cell, \
+             it goes to the fee",
+            inputs_capacity - needed_capacity,
+            change_occupied
+        );
+        Ok(())
+    }
 
-        let change = inputs_capacity - needed_capacity;
-
-        let mut outputs = self.tx.outputs().into_iter().collect::<Vec<_>>();
-        let idx = outputs.len() - 1;
-        let old_capacity: u64 = outputs[idx].capacity().unpack();
-        let new_capacity = old_capacity
-            .checked_add(change)
-            .expect("change cell capacity add overflow");
-        outputs[idx] = self
-            .tx
-            .output(idx)
-            .expect("last output")
-            .as_builder()
-            .capacity(new_capacity.pack())
-            .build();
-
-        self.tx = self.tx.as_advanced_builder().set_outputs(outputs).build();
+    /// An output below its occupied capacity makes the transaction invalid,
+    /// reject it here rather than on sending.
+    fn check_occupied_capacity(&self) -> Result<()> {
+        for (idx, (output, data)) in self.tx.outputs_with_data_iter().enumerate() {
+            let capacity: u64 = output.capacity().unpack();
+            let occupied = output
+                .occupied_capacity(Capacity::bytes(data.len())?)?
+                .as_u64();
+            if capacity < occupied {
+                return Err(CkbTxErr::BelowOccupiedCapacity {
+                    index: idx,
+                    capacity,
+                    occupied,
+                }
+                .into());
+            }
+        }
         Ok(())
     }
 
-    fn fee(tx_size: usize) -> Capacity {
+    pub(crate) fn fee(tx_size: usize) -> Capacity {
         let fee = FEE_RATE.saturating_mul(tx_size as u64) / KB;
         Capacity::shannons(fee)
     }
@@ -5,11 +5,12 @@ use ckb_types::prelude::{Builder, Entity, Pack};
 
 use common::traits::ckb_rpc_client::CkbRpc;
 use common::types::ckb_rpc_client::{Cell, Order, ScriptType, SearchKey, SearchKeyFilter};
-use common::types::tx_builder::{Amount, NetworkType};
+use common::types::tx_builder::{Amount, NetworkType, ScriptKind};
 use common::utils::convert::*;
 
 use crate::ckb::define::constants::TOKEN_BYTES;
 use crate::ckb::define::scripts::*;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
 
@@ -32,7 +33,7 @@ impl Xudt {
     }
 
     pub fn type_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &XUDT_TYPE_MAINNET.tx_hash,
                 XUDT_TYPE_MAINNET.index,
@@ -43,7 +44,8 @@ impl Xudt {
                 XUDT_TYPE_TESTNET.index,
                 XUDT_TYPE_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::Xudt, cell_dep)
     }
 
     pub async fn collect(
@@ -6,17 +6,19 @@ use ckb_types::{H160, H256};
 
 use common::traits::ckb_rpc_client::CkbRpc;
 use common::types::axon_types::delegate::{
-    DelegateArgs, DelegateAtWitness, DelegateInfoDelta, DelegateRequirementArgs,
+    DelegateArgs, DelegateAtWitness, DelegateCellData, DelegateInfoDelta, DelegateRequirementArgs,
     DelegateSmtWitness as ADelegateSmtWitness,
 };
 use common::types::ckb_rpc_client::Cell;
-use common::types::tx_builder::{DelegateItem, NetworkType};
+use common::types::tx_builder::{DelegateItem, NetworkType, ScriptKind};
 use common::utils::convert::*;
 
+use crate::ckb::define::error::CkbTxErr;
 use crate::ckb::define::scripts::*;
 use crate::ckb::define::types::{DelegateSmtUpdateInfo, DelegateSmtWitness, StakeGroupInfo};
 use crate::ckb::helper::ckb::cell_collector::{get_cell_by_scripts, get_cell_by_type};
 use crate::ckb::helper::metadata::Metadata;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::helper::unique_cell_dep;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
@@ -88,7 +90,7 @@ impl Delegate {
     }
 
     pub fn lock_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &DELEGATE_LOCK_MAINNET.tx_hash,
                 DELEGATE_LOCK_MAINNET.index,
@@ -99,11 +101,12 @@ impl Delegate {
                 DELEGATE_LOCK_TESTNET.index,
                 DELEGATE_LOCK_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::DelegateLock, cell_dep)
     }
 
     pub fn smt_type_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &DELEGATE_SMT_TYPE_MAINNET.tx_hash,
                 DELEGATE_SMT_TYPE_MAINNET.index,
@@ -114,7 +117,8 @@ impl Delegate {
                 DELEGATE_SMT_TYPE_TESTNET.index,
                 DELEGATE_SMT_TYPE_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::DelegateSmt, cell_dep)
     }
 
     pub async fn smt_cell_dep(ckb_rpc: &impl CkbRpc, type_id: &H256) -> Result<CellDep> {
@@ -122,7 +126,7 @@ impl Delegate {
     }
 
     pub fn requriement_type_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &DELEGATE_REQUIREMENT_TYPE_MAINNET.tx_hash,
                 DELEGATE_REQUIREMENT_TYPE_MAINNET.index,
@@ -133,7 +137,8 @@ impl Delegate {
                 DELEGATE_REQUIREMENT_TYPE_TESTNET.index,
                 DELEGATE_REQUIREMENT_TYPE_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::DelegateRequirement, cell_dep)
     }
 
     pub fn item(delegate: &DelegateInfoDelta) -> DelegateItem {
@@ -161,6 +166,26 @@ impl Delegate {
         get_cell_by_type(ckb_rpc, delegate_requirement_type).await
     }
 
+    /// The most delegators the staker accepts, from its delegate requirement
+    /// cell.
+    pub async fn maximum_delegators(
+        ckb_rpc: &impl CkbRpc,
+        metadata_type_id: &H256,
+        staker: &H160,
+    ) -> Result<u32> {
+        let cell =
+            Self::get_requirement_cell(ckb_rpc, Self::requirement_type(metadata_type_id, staker))
+                .await?;
+
+        let data = cell
+            .output_data
+            .ok_or_else(|| CkbTxErr::CellDataNotFound("delegate requirement".to_owned()))?;
+        let cell_data = DelegateCellData::new_unchecked(data.into_bytes());
+        Ok(to_u32(
+            &cell_data.delegate_requirement().max_delegator_size(),
+        ))
+    }
+
     pub async fn get_smt_cell(ckb_rpc: &impl CkbRpc, delegate_smt_type: Script) -> Result<Cell> {
         get_cell_by_type(ckb_rpc, delegate_smt_type).await
     }
@@ -5,11 +5,15 @@ use ckb_types::prelude::{Builder, Entity, Pack};
 use ckb_types::H256;
 
 use common::traits::ckb_rpc_client::CkbRpc;
+use common::types::axon_types::metadata::MetadataCellData;
 use common::types::ckb_rpc_client::Cell;
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{NetworkType, ScriptKind};
+use common::utils::convert::to_u16;
 
+use crate::ckb::define::error::CkbTxErr;
 use crate::ckb::define::scripts::*;
 use crate::ckb::helper::ckb::cell_collector::get_cell_by_type;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::helper::unique_cell_dep;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
@@ -34,7 +38,7 @@ impl Metadata {
     }
 
     pub fn type_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &METADATA_TYPE_MAINNET.tx_hash,
                 METADATA_TYPE_MAINNET.index,
@@ -45,7 +49,8 @@ impl Metadata {
                 METADATA_TYPE_TESTNET.index,
                 METADATA_TYPE_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::Metadata, cell_dep)
     }
 
     pub async fn cell_dep(ckb_rpc: &impl CkbRpc, type_id: &H256) -> Result<CellDep> {
@@ -55,4 +60,15 @@ impl Metadata {
     pub async fn get_cell(ckb_rpc: &impl CkbRpc, checkpoint_type: Script) -> Result<Cell> {
         get_cell_by_type(ckb_rpc, checkpoint_type).await
     }
+
+    /// The quorum of the current epoch, from the metadata cell.
+    pub async fn quorum(ckb_rpc: &impl CkbRpc, type_id: &H256) -> Result<u16> {
+        let cell = Self::get_cell(ckb_rpc, Self::type_(type_id)).await?;
+        let data = cell
+            .output_data
+            .ok_or_else(|| CkbTxErr::CellDataNotFound("metadata".to_owned()))?;
+        let cell_data = MetadataCellData::new_unchecked(data.into_bytes());
+        let metadata = cell_data.metadata().get(0).ok_or(CkbTxErr::EmptyMetadata)?;
+        Ok(to_u16(&metadata.quorum()))
+    }
 }
@@ -7,15 +7,27 @@ pub mod rest;
 pub mod stake;
 pub mod withdraw;
 
+use std::collections::HashMap;
+
 use anyhow::Result;
 use ckb_types::{
-    packed::{CellDep, Script as CScript},
-    prelude::{Builder, Entity},
+    packed::{CellDep, OutPoint, Script as CScript},
+    prelude::{Builder, Entity, Pack},
 };
 
 use common::traits::ckb_rpc_client::CkbRpc;
+use common::types::api::ScriptConfig;
+use common::types::tx_builder::{CellDepOverride, NetworkType, ScriptKind};
 
+use crate::ckb::define::scripts::{
+    CHECKPOINT_TYPE_MAINNET, CHECKPOINT_TYPE_TESTNET, DELEGATE_LOCK_MAINNET, DELEGATE_LOCK_TESTNET,
+    DELEGATE_REQUIREMENT_TYPE_MAINNET, DELEGATE_REQUIREMENT_TYPE_TESTNET,
+    DELEGATE_SMT_TYPE_MAINNET, DELEGATE_SMT_TYPE_TESTNET, METADATA_TYPE_MAINNET,
+    METADATA_TYPE_TESTNET, STAKE_LOCK_MAINNET, STAKE_LOCK_TESTNET, STAKE_SMT_TYPE_MAINNET,
+    STAKE_SMT_TYPE_TESTNET, WITHDRAW_LOCK_MAINNET, WITHDRAW_LOCK_TESTNET,
+};
 use crate::ckb::helper::ckb::cell_collector::get_cell_by_type;
+use crate::ckb::{CELL_DEP_OVERRIDES, NETWORK_TYPE};
 
 pub use checkpoint::Checkpoint;
 pub use ckb::*;
@@ -64,6 +76,98 @@ async fn unique_cell_dep(ckb_rpc: &impl CkbRpc, type_id_script: CScript) -> Resu
         .build())
 }
 
+/// Replace the out point of a built-in cell dep with the configured one, if
+/// there is any.
+pub fn override_cell_dep(kind: ScriptKind, cell_dep: CellDep) -> CellDep {
+    apply_cell_dep_override(&CELL_DEP_OVERRIDES.load(), kind, cell_dep)
+}
+
+/// Replace the out point of the cell dep with the one of `overrides`, if it
+/// has one for the kind.
+pub(crate) fn apply_cell_dep_override(
+    overrides: &HashMap<ScriptKind, CellDepOverride>,
+    kind: ScriptKind,
+    cell_dep: CellDep,
+) -> CellDep {
+    match overrides.get(&kind) {
+        Some(o) => cell_dep
+            .as_builder()
+            .out_point(out_point!(&o.tx_hash, o.index))
+            .build(),
+        None => cell_dep,
+    }
+}
+
+/// The stake, delegate, withdraw, metadata and checkpoint scripts of the
+/// current network, with the cell deps the builders use.
+pub fn script_configs() -> Vec<ScriptConfig> {
+    let network = NETWORK_TYPE.load();
+    [
+        (
+            ScriptKind::StakeLock,
+            &*STAKE_LOCK_MAINNET,
+            &*STAKE_LOCK_TESTNET,
+            Stake::lock_dep(),
+        ),
+        (
+            ScriptKind::StakeSmt,
+            &*STAKE_SMT_TYPE_MAINNET,
+            &*STAKE_SMT_TYPE_TESTNET,
+            Stake::smt_type_dep(),
+        ),
+        (
+            ScriptKind::DelegateRequirement,
+            &*DELEGATE_REQUIREMENT_TYPE_MAINNET,
+            &*DELEGATE_REQUIREMENT_TYPE_TESTNET,
+            Delegate::requriement_type_dep(),
+        ),
+        (
+            ScriptKind::DelegateLock,
+            &*DELEGATE_LOCK_MAINNET,
+            &*DELEGATE_LOCK_TESTNET,
+            Delegate::lock_dep(),
+        ),
+        (
+            ScriptKind::DelegateSmt,
+            &*DELEGATE_SMT_TYPE_MAINNET,
+            &*DELEGATE_SMT_TYPE_TESTNET,
+            Delegate::smt_type_dep(),
+        ),
+        (
+            ScriptKind::WithdrawLock,
+            &*WITHDRAW_LOCK_MAINNET,
+            &*WITHDRAW_LOCK_TESTNET,
+            Withdraw::lock_dep(),
+        ),
+        (
+            ScriptKind::Metadata,
+            &*METADATA_TYPE_MAINNET,
+            &*METADATA_TYPE_TESTNET,
+            Metadata::type_dep(),
+        ),
+        (
+            ScriptKind::Checkpoint,
+            &*CHECKPOINT_TYPE_MAINNET,
+            &*CHECKPOINT_TYPE_TESTNET,
+            Checkpoint::type_dep(),
+        ),
+    ]
+    .into_iter()
+    .map(|(kind, mainnet, testnet, cell_dep)| {
+        let script = match **network {
+            NetworkType::Mainnet => mainnet,
+            NetworkType::Testnet => testnet,
+        };
+        ScriptConfig {
+            kind,
+            code_hash: script.code_hash.clone(),
+            hash_type: script.hash_type.into(),
+            cell_dep: cell_dep.into(),
+        }
+    })
+    .collect()
+}
+
 pub fn token_cell_data(amount: u128, extra_args: bytes::Bytes) -> bytes::Bytes {
     let mut res = amount.to_le_bytes().to_vec();
     res.extend(extra_args.to_vec());
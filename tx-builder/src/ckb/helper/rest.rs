@@ -7,11 +7,12 @@ use ckb_types::H256;
 use common::traits::ckb_rpc_client::CkbRpc;
 use common::types::axon_types::selection::SelectionLockArgs;
 use common::types::ckb_rpc_client::Cell;
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{NetworkType, ScriptKind};
 use common::utils::convert::*;
 
 use crate::ckb::define::scripts::*;
 use crate::ckb::helper::ckb::cell_collector::get_cell_by_type;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::helper::TypeId;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
@@ -57,7 +58,7 @@ impl Selection {
     }
 
     pub fn lock_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &SELECTION_LOCK_MAINNET.tx_hash,
                 SELECTION_LOCK_MAINNET.index,
@@ -68,7 +69,8 @@ impl Selection {
                 SELECTION_LOCK_TESTNET.index,
                 SELECTION_LOCK_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::Selection, cell_dep)
     }
 
     pub async fn get_cell(ckb_rpc: &impl CkbRpc, selection_type_id: &H256) -> Result<Cell> {
@@ -94,7 +96,7 @@ impl Reward {
     }
 
     pub fn smt_type_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &REWARD_SMT_TYPE_MAINNET.tx_hash,
                 REWARD_SMT_TYPE_MAINNET.index,
@@ -105,7 +107,8 @@ impl Reward {
                 REWARD_SMT_TYPE_TESTNET.index,
                 REWARD_SMT_TYPE_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::RewardSmt, cell_dep)
     }
 
     pub async fn get_cell(ckb_rpc: &impl CkbRpc, reward_type_id: &H256) -> Result<Cell> {
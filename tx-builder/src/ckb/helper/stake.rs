@@ -1,21 +1,24 @@
 use anyhow::Result;
 use bytes::Bytes;
-use ckb_types::packed::{CellDep, OutPoint, Script, WitnessArgs};
+use ckb_types::packed::{CellDep, CellOutput, OutPoint, Script, WitnessArgs};
 use ckb_types::prelude::{Builder, Entity, Pack};
 use ckb_types::{H160, H256};
 
 use common::traits::ckb_rpc_client::CkbRpc;
 use common::types::axon_types::stake::{
-    StakeArgs, StakeAtWitness, StakeInfoDelta, StakeSmtWitness as AStakeSmtWitness,
+    StakeArgs, StakeAtCellData as AStakeAtCellData, StakeAtWitness, StakeInfoDelta,
+    StakeSmtWitness as AStakeSmtWitness,
 };
 use common::types::ckb_rpc_client::Cell;
-use common::types::tx_builder::{NetworkType, StakeItem};
+use common::types::tx_builder::{NetworkType, ScriptKind, StakeItem};
 use common::utils::convert::*;
 
+use crate::ckb::define::constants::TOKEN_BYTES;
 use crate::ckb::define::scripts::*;
 use crate::ckb::define::types::{StakeInfo, StakeSmtUpdateInfo, StakeSmtWitness};
 use crate::ckb::helper::ckb::cell_collector::{get_cell_by_scripts, get_cell_by_type};
 use crate::ckb::helper::metadata::Metadata;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::helper::unique_cell_dep;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
@@ -62,7 +65,7 @@ impl Stake {
     }
 
     pub fn lock_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &STAKE_LOCK_MAINNET.tx_hash,
                 STAKE_LOCK_MAINNET.index,
@@ -73,11 +76,12 @@ impl Stake {
                 STAKE_LOCK_TESTNET.index,
                 STAKE_LOCK_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::StakeLock, cell_dep)
     }
 
     pub fn smt_type_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &STAKE_SMT_TYPE_MAINNET.tx_hash,
                 STAKE_SMT_TYPE_MAINNET.index,
@@ -88,7 +92,8 @@ impl Stake {
                 STAKE_SMT_TYPE_TESTNET.index,
                 STAKE_SMT_TYPE_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::StakeSmt, cell_dep)
     }
 
     pub async fn smt_cell_dep(ckb_rpc: &impl CkbRpc, type_id: &H256) -> Result<CellDep> {
@@ -103,6 +108,22 @@ impl Stake {
         }
     }
 
+    /// The stake delta of an output, `None` if the output is not a stake AT
+    /// cell.
+    pub fn at_cell_item(output: &CellOutput, data: &[u8]) -> Option<StakeItem> {
+        let code_hash = match **NETWORK_TYPE.load() {
+            NetworkType::Mainnet => &STAKE_LOCK_MAINNET.code_hash,
+            NetworkType::Testnet => &STAKE_LOCK_TESTNET.code_hash,
+        };
+        if output.lock().code_hash().as_slice() != code_hash.as_bytes() || data.len() < TOKEN_BYTES
+        {
+            return None;
+        }
+
+        let stake_data = AStakeAtCellData::from_slice(&data[TOKEN_BYTES..]).ok()?;
+        Some(Self::item(&stake_data.lock().delta()))
+    }
+
     pub async fn get_cell(
         ckb_rpc: &impl CkbRpc,
         stake_lock: Script,
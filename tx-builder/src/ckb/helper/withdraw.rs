@@ -6,7 +6,7 @@ use ckb_types::{H160, H256};
 use common::traits::ckb_rpc_client::CkbRpc;
 use common::types::axon_types::withdraw::{WithdrawArgs, WithdrawWitness};
 use common::types::ckb_rpc_client::Cell;
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{NetworkType, ScriptKind};
 use common::types::{
     axon_types::withdraw::{
         WithdrawAtCellData as AWithdrawAtCellData, WithdrawInfo as AWithdrawInfo,
@@ -17,10 +17,12 @@ use common::types::{
 use common::utils::convert::*;
 
 use crate::ckb::define::constants::TOKEN_BYTES;
+use crate::ckb::define::error::CkbTxErr;
 use crate::ckb::define::scripts::*;
 use crate::ckb::define::types::WithdrawInfo;
 use crate::ckb::helper::ckb::cell_collector::get_cell_by_scripts;
 use crate::ckb::helper::metadata::Metadata;
+use crate::ckb::helper::override_cell_dep;
 use crate::ckb::helper::token_cell_data;
 use crate::ckb::NETWORK_TYPE;
 use crate::{cell_dep, out_point, script};
@@ -51,7 +53,7 @@ impl Withdraw {
     }
 
     pub fn lock_dep() -> CellDep {
-        match **NETWORK_TYPE.load() {
+        let cell_dep = match **NETWORK_TYPE.load() {
             NetworkType::Mainnet => cell_dep!(
                 &WITHDRAW_LOCK_MAINNET.tx_hash,
                 WITHDRAW_LOCK_MAINNET.index,
@@ -62,7 +64,8 @@ impl Withdraw {
                 WITHDRAW_LOCK_TESTNET.index,
                 WITHDRAW_LOCK_TESTNET.dep_type
             ),
-        }
+        };
+        override_cell_dep(ScriptKind::WithdrawLock, cell_dep)
     }
 
     pub async fn get_cell(
@@ -90,11 +93,14 @@ impl Withdraw {
         withdraw_cell: Cell,
         inaugration_epoch: Epoch,
         new_amount: u128,
-    ) -> bytes::Bytes {
-        let mut withdraw_data = withdraw_cell.output_data.unwrap().into_bytes();
-        let mut total_withdraw_amount = new_u128(&withdraw_data[..TOKEN_BYTES]);
-        let withdraw_data =
-            AWithdrawAtCellData::new_unchecked(withdraw_data.split_off(TOKEN_BYTES));
+    ) -> Result<bytes::Bytes> {
+        let withdraw_data = withdraw_cell
+            .output_data
+            .ok_or_else(|| CkbTxErr::CellDataNotFound("withdraw AT".to_owned()))?
+            .into_bytes();
+        let (total_withdraw_amount, withdraw_data) = safe_split(withdraw_data, TOKEN_BYTES)?;
+        let mut total_withdraw_amount = new_u128(&total_withdraw_amount);
+        let withdraw_data = AWithdrawAtCellData::new_unchecked(withdraw_data);
 
         let mut new_withdraw_infos = AWithdrawInfos::new_builder();
         let mut inserted = false;
@@ -122,7 +128,7 @@ impl Withdraw {
 
         let inner_withdraw_data = withdraw_data.lock();
 
-        token_cell_data(
+        Ok(token_cell_data(
             total_withdraw_amount,
             withdraw_data
                 .as_builder()
@@ -134,6 +140,6 @@ impl Withdraw {
                 )
                 .build()
                 .as_bytes(),
-        )
+        ))
     }
 }
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::future::Future;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ckb_jsonrpc_types::{CellInfo, ResponseFormat, TxStatus, Uint32};
+use ckb_types::core::{Capacity, TransactionView};
+use ckb_types::packed::{CellOutput, OutPoint as POutPoint, Script};
+use ckb_types::prelude::{Builder, Entity, Pack, Unpack};
+use ckb_types::{bytes::Bytes, H256};
+
+use common::traits::ckb_rpc_client::CkbRpc;
+use common::types::ckb_rpc_client::{Cell, IndexerTip, Order, Pagination, ScriptType, SearchKey};
+use common::types::{
+    CellWithStatus, JsonBytes, OutPoint, OutputsValidator, Transaction,
+    TransactionWithStatusResponse,
+};
+
+/// Serves a fixed set of live cells, searched by their lock or type script
+/// like the indexer does, all in one page, and a fixed set of pending
+/// transactions. The indexer tip and sending are not served.
+#[derive(Clone, Default)]
+pub struct MockCkbRpc {
+    pub cells: Vec<Cell>,
+    pub txs:   HashMap<H256, TransactionView>,
+}
+
+impl MockCkbRpc {
+    /// A live cell with the data, at its exact occupied capacity if no
+    /// capacity is given.
+    pub fn push(&mut self, output: CellOutput, data: Bytes) {
+        let output = if Unpack::<u64>::unpack(&output.capacity()) == 0 {
+            output
+                .build_exact_capacity(Capacity::bytes(data.len()).unwrap())
+                .unwrap()
+        } else {
+            output
+        };
+        let out_point = POutPoint::new_builder()
+            .tx_hash(H256::from_low_u64_be(self.cells.len() as u64 + 1).pack())
+            .index(0u32.pack())
+            .build();
+        self.cells.push(Cell {
+            output:       output.into(),
+            output_data:  Some(JsonBytes::from_bytes(data)),
+            out_point:    out_point.into(),
+            block_number: 0.into(),
+            tx_index:     0.into(),
+        });
+    }
+
+    /// Pure CKB cells of the lock.
+    pub fn push_capacity(&mut self, lock: Script, capacities: &[u64]) {
+        for capacity in capacities {
+            self.push(
+                CellOutput::new_builder()
+                    .lock(lock.clone())
+                    .capacity(capacity.pack())
+                    .build(),
+                Bytes::default(),
+            );
+        }
+    }
+
+    /// The capacity of the live cell at the out point.
+    pub fn capacity(&self, out_point: &POutPoint) -> u64 {
+        self.cells
+            .iter()
+            .find(|c| &POutPoint::from(c.out_point.clone()) == out_point)
+            .map(|c| u64::from(c.output.capacity))
+            .expect("live cell")
+    }
+
+    /// The fee of the transaction, the capacity of its inputs less that of its
+    /// outputs.
+    pub fn fee(&self, tx: &TransactionView) -> u64 {
+        let inputs_capacity = tx
+            .inputs()
+            .into_iter()
+            .map(|input| self.capacity(&input.previous_output()))
+            .sum::<u64>();
+        let outputs_capacity = tx
+            .outputs()
+            .into_iter()
+            .map(|o| Unpack::<u64>::unpack(&o.capacity()))
+            .sum::<u64>();
+        inputs_capacity - outputs_capacity
+    }
+
+    /// What the pure CKB cells of the lock pay for the outputs of a balanced
+    /// transaction, besides the fee and the change in its last output.
+    pub fn paid_capacity(&self, tx: &TransactionView, lock: &Script) -> u64 {
+        let provided = tx
+            .inputs()
+            .into_iter()
+            .filter_map(|input| {
+                self.cells.iter().find(|c| {
+                    POutPoint::from(c.out_point.clone()) == input.previous_output()
+                        && Script::from(c.output.lock.clone()) == *lock
+                        && c.output.type_.is_none()
+                })
+            })
+            .map(|c| u64::from(c.output.capacity))
+            .sum::<u64>();
+        let change: u64 = tx
+            .output(tx.outputs().len() - 1)
+            .expect("change output")
+            .capacity()
+            .unpack();
+        provided - change - self.fee(tx)
+    }
+
+    /// Build the transaction paying with plenty of pure CKB of the lock, then
+    /// with 1 CKB more than that paid, which can't hold a change cell, and
+    /// check that the 1 CKB goes to the fee instead.
+    pub async fn assert_dust_change<F, Fut>(&self, lock: &Script, build: F)
+    where
+        F: Fn(MockCkbRpc) -> Fut,
+        Fut: Future<Output = Result<TransactionView>>,
+    {
+        let mut rich = self.clone();
+        rich.push_capacity(lock.clone(), &[Capacity::bytes(10_000).unwrap().as_u64()]);
+        let tx = build(rich.clone()).await.unwrap();
+        let paid = rich.paid_capacity(&tx, lock);
+
+        let dust = Capacity::bytes(1).unwrap().as_u64();
+        let mut dusty = self.clone();
+        dusty.push_capacity(lock.clone(), &[paid + dust]);
+        let dusty_tx = build(dusty.clone()).await.unwrap();
+        assert_eq!(dusty_tx.outputs().len(), tx.outputs().len() - 1);
+        assert_eq!(dusty.fee(&dusty_tx), dust);
+    }
+}
+
+#[async_trait]
+impl CkbRpc for MockCkbRpc {
+    async fn get_cells(
+        &self,
+        search_key: SearchKey,
+        _order: Order,
+        _limit: Uint32,
+        _after: Option<JsonBytes>,
+    ) -> Result<Pagination<Cell>> {
+        let filter = search_key.filter.and_then(|f| f.script);
+        let objects = self
+            .cells
+            .iter()
+            .filter(|c| match search_key.script_type {
+                ScriptType::Lock => {
+                    c.output.lock == search_key.script
+                        && filter
+                            .as_ref()
+                            .map_or(true, |f| c.output.type_.as_ref() == Some(f))
+                }
+                ScriptType::Type => {
+                    c.output.type_.as_ref() == Some(&search_key.script)
+                        && filter.as_ref().map_or(true, |f| &c.output.lock == f)
+                }
+            })
+            .map(|c| Cell {
+                output_data: match search_key.with_data {
+                    Some(false) => None,
+                    _ => c.output_data.clone(),
+                },
+                ..c.clone()
+            })
+            .collect();
+        Ok(Pagination {
+            objects,
+            last_cursor: JsonBytes::default(),
+        })
+    }
+
+    async fn get_live_cell(&self, out_point: OutPoint, _with_data: bool) -> Result<CellWithStatus> {
+        let cell = self.cells.iter().find(|c| c.out_point == out_point);
+        Ok(CellWithStatus {
+            cell:   cell.map(|c| CellInfo {
+                output: c.output.clone(),
+                data:   None,
+            }),
+            status: if cell.is_some() { "live" } else { "unknown" }.to_owned(),
+        })
+    }
+
+    async fn get_indexer_tip(&self) -> Result<IndexerTip> {
+        Err(anyhow!("the mock has no indexer tip"))
+    }
+
+    async fn send_transaction(
+        &self,
+        _tx: &Transaction,
+        _outputs_validator: Option<OutputsValidator>,
+    ) -> Result<H256> {
+        Err(anyhow!("the mock can't send transactions"))
+    }
+
+    async fn get_transaction(&self, hash: H256) -> Result<Option<TransactionWithStatusResponse>> {
+        Ok(self.txs.get(&hash).map(|tx| TransactionWithStatusResponse {
+            transaction:        Some(ResponseFormat::json(tx.clone().into())),
+            cycles:             None,
+            time_added_to_pool: None,
+            tx_status:          TxStatus::pending(),
+        }))
+    }
+}
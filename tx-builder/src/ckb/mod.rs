@@ -6,15 +6,21 @@ pub mod helper;
 pub mod init;
 pub mod metadata;
 pub mod mint;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 pub mod reward;
 pub mod stake;
 pub mod stake_smt;
 mod tests;
 pub mod withdraw;
 
+use std::collections::HashMap;
+
 use arc_swap::ArcSwap;
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{CellDepOverride, NetworkType, ScriptKind};
 
 lazy_static::lazy_static! {
     pub static ref NETWORK_TYPE: ArcSwap<NetworkType> = ArcSwap::from_pointee(NetworkType::Testnet);
+    pub static ref CELL_DEP_OVERRIDES: ArcSwap<HashMap<ScriptKind, CellDepOverride>> =
+        ArcSwap::from_pointee(HashMap::new());
 }
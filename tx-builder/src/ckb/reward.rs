@@ -24,7 +24,7 @@ use common::{
 };
 
 use crate::ckb::define::constants::{INAUGURATION, START_EPOCH};
-use crate::ckb::define::error::CkbTxErr;
+use crate::ckb::define::error::{CkbTxErr, CkbTxResult};
 use crate::ckb::define::types::{
     EpochRewardStakeInfo, NotClaimInfo, RewardDelegateInfo, RewardSmtCellData, RewardStakeInfo,
     RewardWitness,
@@ -200,10 +200,11 @@ where
         };
 
         let start_reward_epoch = self.get_start_epoch(&mut witness).await?;
-        let end_reward_epoch = min(
-            self.current_epoch - INAUGURATION,
-            start_reward_epoch + self.info.epoch_count - 1,
-        );
+        let end_reward_epoch = claimable_end_epoch(
+            start_reward_epoch,
+            self.current_epoch,
+            self.info.epoch_count,
+        )?;
 
         let mut total_reward_amount = 0_u128;
         let user = to_eth_h160(&self.user);
@@ -324,6 +325,13 @@ where
             }
         }
 
+        if total_reward_amount == 0 {
+            return Err(CkbTxErr::NothingToClaim {
+                start_epoch: start_reward_epoch,
+                end_epoch:   end_reward_epoch,
+            }
+            .into());
+        }
         wallet_amount += total_reward_amount;
 
         RewardSmtStorage::insert(&self.smt, end_reward_epoch, user).await?;
@@ -388,6 +396,32 @@ where
     }
 }
 
+/// The last epoch whose rewards are claimed, at most `epoch_count` epochs from
+/// `start_epoch` on. The rewards of an epoch unlock `INAUGURATION` epochs
+/// later, so the claim is rejected if the start epoch hasn't unlocked yet.
+pub(crate) fn claimable_end_epoch(
+    start_epoch: Epoch,
+    current_epoch: Epoch,
+    epoch_count: u64,
+) -> CkbTxResult<Epoch> {
+    if epoch_count == 0 {
+        return Err(CkbTxErr::ZeroEpochCount);
+    }
+    if current_epoch < INAUGURATION {
+        return Err(CkbTxErr::EpochTooSmall);
+    }
+
+    let unlocked_epoch = current_epoch - INAUGURATION;
+    if start_epoch > unlocked_epoch {
+        return Err(CkbTxErr::RewardNotUnlocked {
+            start_epoch,
+            unlocked_epoch,
+        });
+    }
+
+    Ok(min(unlocked_epoch, start_epoch + epoch_count - 1))
+}
+
 fn calc_validator_reward(
     total_reward: u128,
     total_amount: u128,
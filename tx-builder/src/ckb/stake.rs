@@ -280,10 +280,9 @@ impl<'a, C: CkbRpc> StakeTxBuilder<'a, C> {
         wallet_amount: Amount,
         stake_data: Bytes,
     ) -> CkbTxResult<Vec<Bytes>> {
-        let total_stake_amount = new_u128(&stake_data[..TOKEN_BYTES]);
-
-        let mut stake_data = stake_data;
-        let stake_data = AStakeAtCellData::new_unchecked(stake_data.split_off(TOKEN_BYTES));
+        let (total_stake_amount, stake_data) = safe_split(stake_data, TOKEN_BYTES)?;
+        let total_stake_amount = new_u128(&total_stake_amount);
+        let stake_data = AStakeAtCellData::new_unchecked(stake_data);
         let last_info =
             ElectAmountCalculator::last_stake_info(&stake_data.lock().delta(), self.current_epoch);
 
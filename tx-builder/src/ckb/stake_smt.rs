@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -22,7 +23,7 @@ use common::types::tx_builder::{
     Amount, Epoch, InStakeSmt, NonTopStakers, PrivateKey, StakeItem, StakeSmtTypeIds,
     Staker as TxStaker,
 };
-use common::utils::convert::new_u128;
+use common::utils::convert::{new_u128, safe_split};
 
 use crate::ckb::define::{
     constants::{INAUGURATION, TOKEN_BYTES},
@@ -30,13 +31,13 @@ use crate::ckb::define::{
     types::StakeInfo,
 };
 use crate::ckb::helper::{
-    token_cell_data, AlwaysSuccess, Checkpoint, Metadata, OmniEth, Secp256k1, Stake, Tx, Withdraw,
-    Xudt,
+    token_cell_data, AlwaysSuccess, Checkpoint, Metadata, OmniEth, PrivateKeySigner, Secp256k1,
+    Signer, Stake, Tx, Withdraw, Xudt,
 };
 
 pub struct StakeSmtTxBuilder<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> {
     ckb:               &'a C,
-    kicker:            PrivateKey,
+    signer:            Arc<dyn Signer>,
     current_epoch:     Epoch,
     quorum:            u16,
     stake_cells:       Vec<Cell>,
@@ -59,7 +60,7 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> IStakeSmtTxBuilder<'a, C,
     ) -> Self {
         Self {
             ckb,
-            kicker,
+            signer: Arc::new(PrivateKeySigner::new(kicker)),
             current_epoch,
             quorum,
             stake_cells,
@@ -136,18 +137,20 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> IStakeSmtTxBuilder<'a, C,
             .witnesses(witnesses.pack())
             .build();
 
-        let omni_eth = OmniEth::new(self.kicker.clone());
-        let kicker_lock = OmniEth::lock(&omni_eth.address()?);
+        let kicker_lock = OmniEth::lock(&self.signer.address()?);
 
         let mut tx = Tx::new(self.ckb, tx);
         tx.balance(kicker_lock.clone()).await?;
 
-        tx.sign(&omni_eth.signer()?, &ScriptGroup {
-            script:         kicker_lock,
-            group_type:     ScriptGroupType::Lock,
-            input_indices:  vec![tx.inner_ref().inputs().len() - 1],
-            output_indices: vec![],
-        })?;
+        tx.sign(
+            &OmniEth::external_signer(Arc::clone(&self.signer))?,
+            &ScriptGroup {
+                script:         kicker_lock,
+                group_type:     ScriptGroupType::Lock,
+                input_indices:  vec![tx.inner_ref().inputs().len() - 1],
+                output_indices: vec![],
+            },
+        )?;
 
         Ok((tx.inner(), statistics.non_top_stakers))
     }
@@ -159,6 +162,12 @@ struct Statistics {
 }
 
 impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> StakeSmtTxBuilder<'a, C, S> {
+    /// Replace the in-process kicker key signer, e.g. with a remote signer.
+    pub fn with_signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = signer;
+        self
+    }
+
     async fn fill_tx(
         &self,
         statistics: &Statistics,
@@ -179,7 +188,7 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> StakeSmtTxBuilder<'a, C, S
 
             witnesses.push(Stake::witness(1).as_bytes());
 
-            let (old_total_stake_amount, old_stake_data) = self.parse_stake_data(stake_cell);
+            let (old_total_stake_amount, old_stake_data) = self.parse_stake_data(stake_cell)?;
 
             let withdraw_lock = Withdraw::lock(&self.type_ids.metadata_type_id, staker);
 
@@ -207,7 +216,7 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> StakeSmtTxBuilder<'a, C, S
                             old_withdraw_cell,
                             self.current_epoch + INAUGURATION,
                             withdraw_amount,
-                        )),
+                        )?),
                     )
                 } else {
                     (old_total_stake_amount, None)
@@ -258,11 +267,17 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> StakeSmtTxBuilder<'a, C, S
         Ok(())
     }
 
-    fn parse_stake_data(&self, cell: &Cell) -> (Amount, StakeAtCellData) {
-        let mut cell_data_bytes = cell.output_data.clone().unwrap().into_bytes();
-        let total_stake_amount = new_u128(&cell_data_bytes[..TOKEN_BYTES]);
-        let stake_data = StakeAtCellData::new_unchecked(cell_data_bytes.split_off(TOKEN_BYTES));
-        (total_stake_amount, stake_data)
+    fn parse_stake_data(&self, cell: &Cell) -> Result<(Amount, StakeAtCellData)> {
+        let cell_data_bytes = cell
+            .output_data
+            .clone()
+            .ok_or_else(|| CkbTxErr::CellDataNotFound("stake AT".to_owned()))?
+            .into_bytes();
+        let (total_stake_amount, stake_data) = safe_split(cell_data_bytes, TOKEN_BYTES)?;
+        Ok((
+            new_u128(&total_stake_amount),
+            StakeAtCellData::new_unchecked(stake_data),
+        ))
     }
 
     async fn update_stake_smt(&self, new_smt: HashMap<SmtStaker, Amount>) -> Result<Root> {
@@ -294,21 +309,21 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> StakeSmtTxBuilder<'a, C, S
         let mut withdraw_amounts = HashMap::new(); // records all the stakers' withdraw amounts
         let mut inputs_stake_cells = HashMap::new();
 
-        for cell in self.stake_cells.clone().into_iter() {
+        for cell in self.stake_cells.iter() {
             let staker = TxStaker::from_slice(
                 &StakeArgs::new_unchecked(cell.output.lock.args.as_bytes().to_owned().into())
                     .stake_addr()
                     .as_bytes(),
             )?;
 
-            let (_, stake_data) = self.parse_stake_data(&cell);
+            let (_, stake_data) = self.parse_stake_data(cell)?;
             let stake_delta = Stake::item(&stake_data.lock().delta());
 
             if stake_delta.inauguration_epoch < self.current_epoch + INAUGURATION {
                 continue;
             }
 
-            inputs_stake_cells.insert(staker.clone(), cell);
+            inputs_stake_cells.insert(staker.clone(), cell.clone());
 
             let smt_staker = SmtStaker::from(staker.0);
             if new_smt.contains_key(&smt_staker) {
@@ -329,7 +344,8 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> StakeSmtTxBuilder<'a, C, S
             }
         }
 
-        let non_top_stakers = self.collect_non_top_stakers(&old_smt, &mut new_smt);
+        let non_top_stakers =
+            collect_non_top_stakers(&old_smt, &mut new_smt, 3 * self.quorum as usize);
 
         for (staker, in_smt) in non_top_stakers.iter() {
             let smt_staker = SmtStaker::from(staker.0);
@@ -394,32 +410,34 @@ impl<'a, C: CkbRpc, S: StakeSmtStorage + Send + Sync> StakeSmtTxBuilder<'a, C, S
             stake_smt_witness,
         ))
     }
+}
 
-    fn collect_non_top_stakers(
-        &self,
-        old_smt: &HashMap<SmtStaker, Amount>,
-        new_smt: &mut HashMap<SmtStaker, Amount>,
-    ) -> NonTopStakers {
-        if new_smt.len() <= 3 * self.quorum as usize {
-            return HashMap::default();
-        }
-
-        let mut all_stakes = new_smt
-            .clone()
-            .into_iter()
-            .map(|(k, v)| (k, v))
-            .collect::<Vec<(SmtStaker, Amount)>>();
-        all_stakes.sort_unstable_by_key(|v| v.1);
-
-        let delete_count = all_stakes.len() - 3 * self.quorum as usize;
-        let non_top_stakers = &all_stakes[..delete_count];
-
-        non_top_stakers
-            .iter()
-            .map(|(staker, _)| {
-                new_smt.remove(staker);
-                (TxStaker::from(staker.0), old_smt.contains_key(staker))
-            })
-            .collect()
+/// Remove the stakers with the smallest amounts from `new_smt` until
+/// `top_count` are left, and return them with whether they were in `old_smt`.
+/// Only the smallest ones are partitioned out, without sorting or copying the
+/// whole SMT.
+pub(crate) fn collect_non_top_stakers(
+    old_smt: &HashMap<SmtStaker, Amount>,
+    new_smt: &mut HashMap<SmtStaker, Amount>,
+    top_count: usize,
+) -> NonTopStakers {
+    if new_smt.len() <= top_count {
+        return HashMap::default();
     }
+
+    let delete_count = new_smt.len() - top_count;
+    let mut all_stakes = new_smt
+        .iter()
+        .map(|(k, v)| (*k, *v))
+        .collect::<Vec<(SmtStaker, Amount)>>();
+    all_stakes.select_nth_unstable_by_key(delete_count - 1, |v| v.1);
+    all_stakes.truncate(delete_count);
+
+    all_stakes
+        .into_iter()
+        .map(|(staker, _)| {
+            new_smt.remove(&staker);
+            (TxStaker::from(staker.0), old_smt.contains_key(&staker))
+        })
+        .collect()
 }
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ckb_types::h256;
+    use ckb_types::packed::OutPoint;
+    use ckb_types::prelude::{Builder, Entity, Pack};
+
+    use common::types::tx_builder::{CellDepOverride, ScriptKind};
+
+    use crate::ckb::define::scripts::{OMNI_LOCK_TESTNET, STAKE_LOCK_TESTNET};
+    use crate::ckb::helper::{apply_cell_dep_override, script_configs, Checkpoint, OmniEth};
+    use crate::out_point;
+
+    #[test]
+    fn overridden_cell_dep() {
+        // The overrides are passed in rather than set globally, which would
+        // race with the other builder tests.
+        let tx_hash = h256!("0x0101010101010101010101010101010101010101010101010101010101010101");
+        let overrides = HashMap::from([(ScriptKind::Checkpoint, CellDepOverride {
+            tx_hash: tx_hash.clone(),
+            index:   3,
+        })]);
+
+        let checkpoint =
+            apply_cell_dep_override(&overrides, ScriptKind::Checkpoint, Checkpoint::type_dep());
+        assert_eq!(checkpoint.out_point(), out_point!(&tx_hash, 3u32));
+
+        let omni_lock =
+            apply_cell_dep_override(&overrides, ScriptKind::OmniLock, OmniEth::lock_dep());
+        assert_eq!(
+            omni_lock.out_point(),
+            out_point!(&OMNI_LOCK_TESTNET.tx_hash, OMNI_LOCK_TESTNET.index)
+        );
+    }
+
+    #[test]
+    fn script_configs_match_statics() {
+        let configs = script_configs();
+        assert_eq!(configs.len(), 8);
+
+        let stake_lock = configs
+            .iter()
+            .find(|c| c.kind == ScriptKind::StakeLock)
+            .unwrap();
+        assert_eq!(stake_lock.code_hash, STAKE_LOCK_TESTNET.code_hash);
+        assert_eq!(
+            stake_lock.hash_type,
+            STAKE_LOCK_TESTNET.hash_type.clone().into()
+        );
+        assert_eq!(
+            stake_lock.cell_dep.out_point.tx_hash,
+            STAKE_LOCK_TESTNET.tx_hash
+        );
+        assert_eq!(
+            stake_lock.cell_dep.out_point.index,
+            STAKE_LOCK_TESTNET.index.into()
+        );
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use ckb_types::bytes::Bytes;
+    use ckb_types::packed::CellOutput;
+    use ckb_types::prelude::{Builder, Entity, Pack};
+    use ckb_types::{h160, H160};
+
+    use common::traits::tx_builder::IDelegateTxBuilder;
+    use common::types::tx_builder::{DelegateItem, DelegateItemError, StakeTypeIds};
+    use rpc_client::ckb_client::ckb_rpc_client::CkbRpcClient;
+
+    use crate::ckb::define::error::CkbTxErr;
+    use crate::ckb::delegate::DelegateTxBuilder;
+    use crate::ckb::helper::{Delegate, OmniEth, Xudt};
+    use crate::ckb::mock::MockCkbRpc;
+
+    #[test]
+    fn invalid_delegate_item() {
+        let ckb_client = CkbRpcClient::new("https://testnet.ckb.dev");
+        let staker = h160!("0x0000000000000000000000000000000000000001");
+        let delegators = vec![
+            DelegateItem::new_for_delegate(staker.clone(), true, 100, 3),
+            DelegateItem::new_for_delegate(staker.clone(), false, 50, 3),
+            DelegateItem::new_for_delegate(H160::default(), true, 100, 3),
+            DelegateItem::new_for_delegate(staker, true, 0, 3),
+        ];
+
+        let builder = DelegateTxBuilder::new(
+            &ckb_client,
+            StakeTypeIds::default(),
+            H160::default(),
+            1,
+            delegators.clone(),
+        );
+        let err = builder.check_delegators().unwrap_err();
+        assert!(matches!(err, CkbTxErr::DelegateItem {
+            index:  2,
+            source: DelegateItemError::ZeroStaker,
+        }));
+
+        let builder = DelegateTxBuilder::new(
+            &ckb_client,
+            StakeTypeIds::default(),
+            H160::default(),
+            1,
+            vec![delegators[0].clone(), delegators[3].clone()],
+        );
+        let err = builder.check_delegators().unwrap_err();
+        assert!(matches!(err, CkbTxErr::DelegateItem {
+            index:  1,
+            source: DelegateItemError::ZeroAmount,
+        }));
+
+        let builder = DelegateTxBuilder::new(
+            &ckb_client,
+            StakeTypeIds::default(),
+            H160::default(),
+            1,
+            delegators[..2].to_vec(),
+        );
+        assert!(builder.check_delegators().is_ok());
+    }
+
+    fn builder<'a>(
+        rpc: &'a MockCkbRpc,
+        type_ids: &StakeTypeIds,
+        delegator: &H160,
+        staker: &H160,
+    ) -> DelegateTxBuilder<'a, MockCkbRpc> {
+        DelegateTxBuilder::new(rpc, type_ids.clone(), delegator.clone(), 1, vec![
+            DelegateItem::new_for_delegate(staker.clone(), true, 100, 3),
+        ])
+    }
+
+    #[tokio::test]
+    async fn first_delegate_dust_change() {
+        let delegator = &h160!("0x0000000000000000000000000000000000000002");
+        let staker = &h160!("0x0000000000000000000000000000000000000001");
+        let type_ids = &StakeTypeIds::default();
+        let token_lock = OmniEth::lock(delegator);
+        let mut rpc = MockCkbRpc::default();
+        rpc.push(
+            CellOutput::new_builder()
+                .lock(token_lock.clone())
+                .type_(Some(Xudt::type_(&type_ids.xudt_owner.pack())).pack())
+                .build(),
+            1000u128.to_le_bytes().to_vec().into(),
+        );
+        rpc.assert_dust_change(&token_lock, |rpc| async move {
+            builder(&rpc, type_ids, delegator, staker).build_tx().await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn maximum_delegators_without_data() {
+        let staker = h160!("0x0000000000000000000000000000000000000001");
+        let type_ids = StakeTypeIds::default();
+        let mut rpc = MockCkbRpc::default();
+        rpc.push(
+            CellOutput::new_builder()
+                .lock(OmniEth::lock(&staker))
+                .type_(
+                    Some(Delegate::requirement_type(
+                        &type_ids.metadata_type_id,
+                        &staker,
+                    ))
+                    .pack(),
+                )
+                .build(),
+            Bytes::default(),
+        );
+        rpc.cells[0].output_data = None;
+
+        let err = Delegate::maximum_delegators(&rpc, &type_ids.metadata_type_id, &staker)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CkbTxErr>(),
+            Some(CkbTxErr::CellDataNotFound(_))
+        ));
+    }
+}
@@ -1,2 +1,9 @@
+mod cell_dep;
+mod delegate;
 mod omni;
+mod reward;
+mod signer;
+mod stake;
+mod stake_smt;
+mod tx;
 mod withdraw;
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use ckb_types::h256;
+    use ckb_types::{h160, h256};
+
+    use common::types::tx_builder::NetworkType;
 
     use crate::ckb::helper::ckb::omni::OmniEth;
 
@@ -22,4 +24,17 @@ mod tests {
         let omni_eth = OmniEth::new(test_key);
         println!("ckb address: {}", omni_eth.ckb_address().unwrap());
     }
+
+    #[test]
+    fn omni_address() {
+        let addr = h160!("0x0000000000000000000000000000000000000001");
+        assert_eq!(
+            OmniEth::address_of(&addr, &NetworkType::Mainnet),
+            "ckb1qzdcr9un5ezx8tkh03s46m9jymh22jruelq8svzr5krj2nx69dhjvqgpqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqpqqpculsn"
+        );
+        assert_eq!(
+            OmniEth::address_of(&addr, &NetworkType::Testnet),
+            "ckt1qrejnmlar3r452tcg57gvq8patctcgy8acync0hxfnyka35ywafvkqgpqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqpqqkypl48"
+        );
+    }
 }
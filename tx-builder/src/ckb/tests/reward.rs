@@ -0,0 +1,179 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ckb_types::core::Capacity;
+    use ckb_types::packed::CellOutput;
+    use ckb_types::prelude::{Builder, Entity, Pack};
+    use ckb_types::{bytes::Bytes, h160, H160};
+
+    use common::traits::smt::{ProposalSmtStorage, StakeSmtStorage};
+    use common::traits::tx_builder::IRewardTxBuilder;
+    use common::types::axon_types::delegate::DelegateRequirement as ADelegateRequirement;
+    use common::types::smt::UserAmount;
+    use common::types::tx_builder::{DelegateRequirement, Epoch, RewardInfo, RewardTypeIds};
+    use common::utils::convert::to_eth_h160;
+    use storage::SmtManager;
+
+    use crate::ckb::define::error::CkbTxErr;
+    use crate::ckb::helper::{
+        AlwaysSuccess, Checkpoint, Delegate, Metadata, OmniEth, Reward, Selection, Stake,
+    };
+    use crate::ckb::mock::MockCkbRpc;
+    use crate::ckb::reward::{claimable_end_epoch, RewardTxBuilder};
+
+    static ROCKSDB_PATH: &str = "./free-space/smt";
+
+    #[test]
+    fn reward_claim_epochs() {
+        assert!(matches!(
+            claimable_end_epoch(0, 5, 0),
+            Err(CkbTxErr::ZeroEpochCount)
+        ));
+        assert!(matches!(
+            claimable_end_epoch(0, 1, 10),
+            Err(CkbTxErr::EpochTooSmall)
+        ));
+
+        // The rewards up to epoch 3 are unlocked at epoch 5.
+        assert!(matches!(
+            claimable_end_epoch(4, 5, 10),
+            Err(CkbTxErr::RewardNotUnlocked {
+                start_epoch:    4,
+                unlocked_epoch: 3,
+            })
+        ));
+
+        assert_eq!(claimable_end_epoch(3, 5, 10).unwrap(), 3);
+        assert_eq!(claimable_end_epoch(0, 5, 10).unwrap(), 3);
+        assert_eq!(claimable_end_epoch(0, 20, 10).unwrap(), 9);
+    }
+
+    /// An SMT of its own for each test, empty at the start of every run.
+    fn smt(name: &str) -> SmtManager {
+        let mut path = PathBuf::from(ROCKSDB_PATH);
+        path.push(name);
+        let _ = std::fs::remove_dir_all(&path);
+        SmtManager::new(path)
+    }
+
+    /// The reward SMT, selection, checkpoint, metadata, stake SMT and delegate
+    /// SMT cells, the delegate requirement cell of the validator and the CKB
+    /// of the user.
+    fn mock_cells(type_ids: &RewardTypeIds, validator: &H160, user: &H160) -> MockCkbRpc {
+        let mut rpc = MockCkbRpc::default();
+        for type_ in [
+            Reward::smt_type(&type_ids.reward_smt_type_id),
+            Selection::type_(&type_ids.selection_type_id),
+            Checkpoint::type_(&type_ids.checkpoint_type_id),
+            Metadata::type_(&type_ids.metadata_type_id),
+            Stake::smt_type(&type_ids.metadata_type_id),
+            Delegate::smt_type(&type_ids.metadata_type_id),
+        ] {
+            rpc.push(
+                CellOutput::new_builder()
+                    .lock(AlwaysSuccess::lock())
+                    .type_(Some(type_).pack())
+                    .build(),
+                Bytes::from(vec![0u8; 32]),
+            );
+        }
+        rpc.push(
+            CellOutput::new_builder()
+                .lock(OmniEth::lock(validator))
+                .type_(
+                    Some(Delegate::requirement_type(
+                        &type_ids.metadata_type_id,
+                        validator,
+                    ))
+                    .pack(),
+                )
+                .build(),
+            ADelegateRequirement::from(DelegateRequirement {
+                commission_rate: 20,
+                ..Default::default()
+            })
+            .as_bytes(),
+        );
+        rpc.push_capacity(OmniEth::lock(user), &[Capacity::bytes(10_000)
+            .unwrap()
+            .as_u64()]);
+        rpc
+    }
+
+    fn builder<'a>(
+        rpc: &'a MockCkbRpc,
+        type_ids: &RewardTypeIds,
+        smt: SmtManager,
+        user: &H160,
+        current_epoch: Epoch,
+    ) -> RewardTxBuilder<'a, MockCkbRpc, SmtManager> {
+        RewardTxBuilder::new(
+            rpc,
+            type_ids.clone(),
+            smt,
+            RewardInfo {
+                base_reward:               1000,
+                half_reward_cycle:         100,
+                theoretical_propose_count: 10,
+                epoch_count:               1,
+            },
+            user.clone(),
+            current_epoch,
+        )
+    }
+
+    #[tokio::test]
+    async fn reward_nothing_to_claim() {
+        let user = h160!("0x0000000000000000000000000000000000000001");
+        let type_ids = RewardTypeIds::default();
+        let rpc = mock_cells(&type_ids, &user, &user);
+
+        // No block was proposed at epoch 0, so there is no reward in it.
+        let err = builder(&rpc, &type_ids, smt("reward_nothing"), &user, 2)
+            .build_tx()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CkbTxErr>(),
+            Some(CkbTxErr::NothingToClaim {
+                start_epoch: 0,
+                end_epoch:   0,
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn reward_mature_claim() {
+        let user = h160!("0x0000000000000000000000000000000000000001");
+        let type_ids = RewardTypeIds::default();
+        let rpc = mock_cells(&type_ids, &user, &user);
+
+        // The user proposed every block of epoch 0 as the only validator, with
+        // the stake of epoch 2 taking effect then.
+        let smt = smt("reward_mature");
+        ProposalSmtStorage::insert(&smt, 0, vec![(to_eth_h160(&user), 10)])
+            .await
+            .unwrap();
+        StakeSmtStorage::insert(&smt, 2, vec![UserAmount {
+            user:        to_eth_h160(&user),
+            amount:      100,
+            is_increase: true,
+        }])
+        .await
+        .unwrap();
+
+        let tx = builder(&rpc, &type_ids, smt, &user, 3)
+            .build_tx()
+            .await
+            .unwrap();
+
+        // The reward SMT, AT, selection and change cells.
+        assert_eq!(tx.outputs().len(), 4);
+        assert_eq!(
+            tx.outputs_data().get(1).unwrap().raw_data(),
+            Bytes::from(1000u128.to_le_bytes().to_vec())
+        );
+        assert_eq!(tx.output(1).unwrap().lock(), OmniEth::lock(&user));
+    }
+}
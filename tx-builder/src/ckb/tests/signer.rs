@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anyhow::Result;
+    use ckb_sdk::traits::Signer as CkbSigner;
+    use ckb_sdk::types::omni_lock::OmniLockWitnessLock;
+    use ckb_types::core::{Capacity, TransactionBuilder, TransactionView};
+    use ckb_types::packed::{CellInput, CellOutput, OutPoint, WitnessArgs};
+    use ckb_types::prelude::{Builder, Entity, Pack};
+    use ckb_types::{bytes::Bytes, h160, h256, H160, H256};
+
+    use crate::ckb::helper::ckb::signer::{PrivateKeySigner, Signer, SignerAdapter};
+    use crate::ckb::helper::{OmniEth, Tx};
+    use crate::ckb::mock::MockCkbRpc;
+
+    #[derive(Default)]
+    struct MockSigner {
+        messages: Mutex<Vec<H256>>,
+    }
+
+    impl Signer for MockSigner {
+        fn address(&self) -> Result<H160> {
+            Ok(h160!("0x0000000000000000000000000000000000000001"))
+        }
+
+        fn sign(&self, message: &H256) -> Result<Bytes> {
+            self.messages.lock().unwrap().push(message.clone());
+            Ok(Bytes::from(vec![7u8; 65]))
+        }
+    }
+
+    /// A transaction spending a CKB cell of the omni lock of the address.
+    fn omni_tx(rpc: &mut MockCkbRpc, addr: &H160) -> TransactionView {
+        let lock = OmniEth::lock(addr);
+        rpc.push_capacity(lock.clone(), &[Capacity::bytes(100).unwrap().as_u64()]);
+        TransactionBuilder::default()
+            .input(
+                CellInput::new_builder()
+                    .previous_output(OutPoint::from(rpc.cells[0].out_point.clone()))
+                    .build(),
+            )
+            .output(
+                CellOutput::new_builder()
+                    .lock(lock)
+                    .capacity(Capacity::bytes(99).unwrap().pack())
+                    .build(),
+            )
+            .output_data(Bytes::default().pack())
+            .witness(OmniEth::witness_placeholder().as_bytes().pack())
+            .build()
+    }
+
+    /// Sign every lock group the way the builders do.
+    async fn sign(
+        rpc: &MockCkbRpc,
+        tx: TransactionView,
+        signer: Arc<dyn Signer>,
+    ) -> TransactionView {
+        let mut tx = Tx::new(rpc, tx);
+        let omni_signer = OmniEth::external_signer(signer).unwrap();
+        let script_groups = tx.gen_script_group().await.unwrap();
+        for group in script_groups.lock_groups.values() {
+            tx.sign(&omni_signer, group).unwrap();
+        }
+        tx.inner()
+    }
+
+    fn signature(tx: &TransactionView) -> Bytes {
+        let witness = WitnessArgs::from_slice(&tx.witnesses().get(0).unwrap().raw_data()).unwrap();
+        let lock = witness.lock().to_opt().unwrap().raw_data();
+        OmniLockWitnessLock::from_slice(&lock)
+            .unwrap()
+            .signature()
+            .to_opt()
+            .unwrap()
+            .raw_data()
+    }
+
+    #[tokio::test]
+    async fn mock_signer() {
+        let mock = Arc::new(MockSigner::default());
+        let adapter = SignerAdapter::new(Arc::clone(&mock) as Arc<dyn Signer>);
+        let id = mock.address().unwrap();
+        assert!(adapter.match_id(id.as_bytes()));
+        assert!(!adapter.match_id(&[0u8; 20]));
+
+        let mut rpc = MockCkbRpc::default();
+        let tx = omni_tx(&mut rpc, &id);
+        let tx = sign(&rpc, tx, Arc::clone(&mock) as Arc<dyn Signer>).await;
+
+        assert_eq!(mock.messages.lock().unwrap().len(), 1);
+        assert_eq!(signature(&tx), Bytes::from(vec![7u8; 65]));
+    }
+
+    #[tokio::test]
+    async fn private_key_signer() {
+        let key = h256!("0x13b08bb054d5dd04013156dced8ba2ce4d8cc5973e10d905a228ea1abc267e60");
+        let omni_eth = OmniEth::new(key.clone());
+        let mut rpc = MockCkbRpc::default();
+        let tx = omni_tx(&mut rpc, &omni_eth.address().unwrap());
+
+        // The external signer signs just like the in process one of the key.
+        let signed = sign(&rpc, tx.clone(), Arc::new(PrivateKeySigner::new(key))).await;
+        let mut expected = Tx::new(&rpc, tx);
+        let script_groups = expected.gen_script_group().await.unwrap();
+        for group in script_groups.lock_groups.values() {
+            expected.sign(&omni_eth.signer().unwrap(), group).unwrap();
+        }
+
+        assert_eq!(signature(&signed).len(), 65);
+        assert_eq!(
+            signed.witnesses().as_bytes(),
+            expected.inner().witnesses().as_bytes()
+        );
+    }
+}
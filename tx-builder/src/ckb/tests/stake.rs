@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use ckb_types::packed::CellOutput;
+    use ckb_types::prelude::{Builder, Entity, Pack};
+    use ckb_types::{h160, H160};
+
+    use common::traits::tx_builder::IStakeTxBuilder;
+    use common::types::tx_builder::{FirstStakeInfo, StakeItem, StakeTypeIds};
+
+    use crate::ckb::helper::{OmniEth, Xudt};
+    use crate::ckb::mock::MockCkbRpc;
+    use crate::ckb::stake::StakeTxBuilder;
+
+    fn builder<'a>(
+        rpc: &'a MockCkbRpc,
+        type_ids: &StakeTypeIds,
+        staker: &H160,
+    ) -> StakeTxBuilder<'a, MockCkbRpc> {
+        StakeTxBuilder::new(
+            rpc,
+            type_ids.clone(),
+            staker.clone(),
+            1,
+            StakeItem {
+                is_increase:        true,
+                amount:             100,
+                inauguration_epoch: 3,
+            },
+            Some(FirstStakeInfo {
+                l1_pub_key:  Default::default(),
+                bls_pub_key: Default::default(),
+                delegate:    Default::default(),
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn first_stake_dust_change() {
+        let staker = &h160!("0x0000000000000000000000000000000000000001");
+        let type_ids = &StakeTypeIds::default();
+        let token_lock = OmniEth::lock(staker);
+        let mut rpc = MockCkbRpc::default();
+        rpc.push(
+            CellOutput::new_builder()
+                .lock(token_lock.clone())
+                .type_(Some(Xudt::type_(&type_ids.xudt_owner.pack())).pack())
+                .build(),
+            1000u128.to_le_bytes().to_vec().into(),
+        );
+        rpc.assert_dust_change(&token_lock, |rpc| async move {
+            builder(&rpc, type_ids, staker).build_tx().await
+        })
+        .await;
+    }
+}
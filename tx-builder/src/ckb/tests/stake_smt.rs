@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::types::smt::Staker as SmtStaker;
+    use common::types::tx_builder::Staker as TxStaker;
+
+    use crate::ckb::stake_smt::collect_non_top_stakers;
+
+    #[test]
+    fn non_top_stakers_of_large_set() {
+        let staker = |i: u64| SmtStaker::from_low_u64_be(i);
+        // Every other staker was in the old SMT.
+        let old_smt = (0..10_000)
+            .step_by(2)
+            .map(|i| (staker(i), i as u128))
+            .collect::<HashMap<_, _>>();
+        let mut new_smt = (0..10_000)
+            .map(|i| (staker(i), i as u128))
+            .collect::<HashMap<_, _>>();
+
+        let non_top = collect_non_top_stakers(&old_smt, &mut new_smt, 30);
+
+        assert_eq!(new_smt.len(), 30);
+        assert!(new_smt.values().all(|amount| *amount >= 9_970));
+        assert_eq!(non_top.len(), 9_970);
+        for i in [0, 1, 9_969] {
+            let in_smt = non_top[&TxStaker::from(staker(i).0)];
+            assert_eq!(in_smt, i % 2 == 0);
+        }
+
+        let mut small_smt = new_smt.clone();
+        assert!(collect_non_top_stakers(&old_smt, &mut small_smt, 30).is_empty());
+        assert_eq!(small_smt, new_smt);
+    }
+}
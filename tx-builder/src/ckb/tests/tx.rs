@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use ckb_types::core::{Capacity, TransactionBuilder};
+    use ckb_types::h160;
+    use ckb_types::packed::{CellOutput, Script};
+    use ckb_types::prelude::{Builder, Entity, Pack, Unpack};
+
+    use crate::ckb::define::error::CkbTxErr;
+    use crate::ckb::helper::{OmniEth, Tx};
+    use crate::ckb::mock::MockCkbRpc;
+
+    fn unbalanced_tx(lock: Script) -> ckb_types::core::TransactionView {
+        TransactionBuilder::default()
+            .output(
+                CellOutput::new_builder()
+                    .lock(lock)
+                    .build_exact_capacity(Capacity::bytes(16).unwrap())
+                    .unwrap(),
+            )
+            .output_data(vec![0u8; 16].pack())
+            .witness(OmniEth::witness_placeholder().as_bytes().pack())
+            .build()
+    }
+
+    #[tokio::test]
+    async fn balance_fee_and_capacity() {
+        let provider = OmniEth::lock(&h160!("0x0000000000000000000000000000000000000001"));
+        let user = OmniEth::lock(&h160!("0x0000000000000000000000000000000000000002"));
+        let mut rpc = MockCkbRpc::default();
+        rpc.push_capacity(provider.clone(), &[
+            Capacity::bytes(100).unwrap().as_u64(),
+            Capacity::bytes(1).unwrap().as_u64(),
+            Capacity::bytes(1000).unwrap().as_u64(),
+        ]);
+
+        let mut tx = Tx::new(&rpc, unbalanced_tx(user));
+        tx.balance(provider).await.unwrap();
+        let tx = tx.inner();
+
+        for (output, data) in tx.outputs_with_data_iter() {
+            let capacity: u64 = output.capacity().unpack();
+            let occupied = output
+                .occupied_capacity(Capacity::bytes(data.len()).unwrap())
+                .unwrap();
+            assert!(capacity >= occupied.as_u64());
+        }
+
+        let fee = rpc.fee(&tx);
+        let tx_size = tx.data().as_reader().serialized_size_in_block();
+        assert!(fee > 0);
+        assert_eq!(fee, Tx::<MockCkbRpc>::fee(tx_size).as_u64());
+    }
+
+    #[tokio::test]
+    async fn balance_insufficient_capacity() {
+        let provider = OmniEth::lock(&h160!("0x0000000000000000000000000000000000000001"));
+        let user = OmniEth::lock(&h160!("0x0000000000000000000000000000000000000002"));
+        let mut rpc = MockCkbRpc::default();
+        rpc.push_capacity(provider.clone(), &[Capacity::bytes(10).unwrap().as_u64()]);
+
+        let mut tx = Tx::new(&rpc, unbalanced_tx(user));
+        let err = tx.balance(provider).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CkbTxErr>(),
+            Some(CkbTxErr::InsufficientCapacity { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn balance_dust_change_to_fee() {
+        let provider = OmniEth::lock(&h160!("0x0000000000000000000000000000000000000001"));
+        let user = OmniEth::lock(&h160!("0x0000000000000000000000000000000000000002"));
+        let tx = unbalanced_tx(user);
+        let outputs_capacity: u64 = tx.output(0).unwrap().capacity().unpack();
+
+        // 1 CKB over the outputs pays the fee, but can't hold a change cell.
+        let mut rpc = MockCkbRpc::default();
+        rpc.push_capacity(provider.clone(), &[
+            outputs_capacity + Capacity::bytes(1).unwrap().as_u64()
+        ]);
+
+        let mut tx = Tx::new(&rpc, tx);
+        tx.balance(provider).await.unwrap();
+        let tx = tx.inner();
+
+        assert_eq!(tx.outputs().len(), 1);
+        let tx_size = tx.data().as_reader().serialized_size_in_block();
+        assert_eq!(rpc.fee(&tx), Capacity::bytes(1).unwrap().as_u64());
+        assert!(rpc.fee(&tx) >= Tx::<MockCkbRpc>::fee(tx_size).as_u64());
+    }
+}
@@ -1,14 +1,139 @@
 #[cfg(test)]
 mod tests {
-    use ckb_types::h256;
+    use ckb_types::bytes::Bytes;
+    use ckb_types::packed::{CellOutput, Script};
+    use ckb_types::prelude::{Builder, Entity, Pack};
+    use ckb_types::{h160, h256, H160};
 
     use common::traits::tx_builder::IWithdrawTxBuilder;
+    use common::types::axon_types::withdraw::{
+        WithdrawAtCellData, WithdrawInfo as AWithdrawInfo, WithdrawInfos,
+    };
     use common::types::tx_builder::{Epoch, StakeTypeIds};
+    use common::utils::convert::new_u128;
     use rpc_client::ckb_client::ckb_rpc_client::CkbRpcClient;
 
+    use crate::ckb::define::constants::TOKEN_BYTES;
+    use crate::ckb::define::error::CkbTxErr;
+    use crate::ckb::define::types::WithdrawInfo;
     use crate::ckb::helper::ckb::{OmniEth, Tx};
+    use crate::ckb::helper::{token_cell_data, Checkpoint, Metadata, Withdraw, Xudt};
+    use crate::ckb::mock::MockCkbRpc;
     use crate::ckb::withdraw::WithdrawTxBuilder;
 
+    fn withdraw_data(total_amount: u128, withdraw_infos: Vec<(Epoch, u128)>) -> Bytes {
+        let data = WithdrawAtCellData::default();
+        let withdraw_infos = withdraw_infos
+            .into_iter()
+            .fold(WithdrawInfos::new_builder(), |infos, (epoch, amount)| {
+                infos.push(AWithdrawInfo::from(WithdrawInfo { epoch, amount }))
+            })
+            .build();
+        let lock = data
+            .lock()
+            .as_builder()
+            .withdraw_infos(withdraw_infos)
+            .build();
+        token_cell_data(
+            total_amount,
+            data.as_builder().lock(lock).build().as_bytes(),
+        )
+    }
+
+    #[test]
+    fn nothing_to_withdraw() {
+        let ckb_client = CkbRpcClient::new("https://testnet.ckb.dev");
+        let builder =
+            WithdrawTxBuilder::new(&ckb_client, StakeTypeIds::default(), H160::default(), 3);
+
+        let err = builder
+            .build_withdraw_data(withdraw_data(0, vec![]))
+            .unwrap_err();
+        assert!(matches!(err, CkbTxErr::NothingToWithdraw(3)));
+
+        let err = builder
+            .build_withdraw_data(withdraw_data(100, vec![(5, 100)]))
+            .unwrap_err();
+        assert!(matches!(err, CkbTxErr::NothingToWithdraw(3)));
+
+        let (data, unlock_amount) = builder
+            .build_withdraw_data(withdraw_data(130, vec![(2, 30), (5, 100)]))
+            .unwrap();
+        assert_eq!(unlock_amount, 30);
+        assert_eq!(new_u128(&data[..TOKEN_BYTES]), 100);
+
+        let err = builder
+            .build_withdraw_data(Bytes::from(vec![0; TOKEN_BYTES - 1]))
+            .unwrap_err();
+        assert!(matches!(err, CkbTxErr::CellData(_)));
+    }
+
+    #[tokio::test]
+    async fn withdraw_dust_change() {
+        let user = &h160!("0x0000000000000000000000000000000000000001");
+        let type_ids = &StakeTypeIds::default();
+        let token_lock = OmniEth::lock(user);
+        let xudt = Xudt::type_(&type_ids.xudt_owner.pack());
+        let mut rpc = MockCkbRpc::default();
+        rpc.push(
+            CellOutput::new_builder()
+                .lock(Withdraw::lock(&type_ids.metadata_type_id, user))
+                .type_(Some(xudt.clone()).pack())
+                .build(),
+            withdraw_data(130, vec![(2, 30), (5, 100)]),
+        );
+        rpc.push(
+            CellOutput::new_builder()
+                .lock(token_lock.clone())
+                .type_(Some(xudt).pack())
+                .build(),
+            1000u128.to_le_bytes().to_vec().into(),
+        );
+        for type_ in [
+            Checkpoint::type_(&type_ids.checkpoint_type_id),
+            Metadata::type_(&type_ids.metadata_type_id),
+        ] {
+            rpc.push(
+                CellOutput::new_builder()
+                    .lock(Script::default())
+                    .type_(Some(type_).pack())
+                    .build(),
+                Bytes::default(),
+            );
+        }
+
+        rpc.assert_dust_change(&token_lock, |rpc| async move {
+            WithdrawTxBuilder::new(&rpc, type_ids.clone(), user.clone(), 3)
+                .build_tx()
+                .await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn withdraw_without_data() {
+        let user = h160!("0x0000000000000000000000000000000000000001");
+        let type_ids = StakeTypeIds::default();
+        let mut rpc = MockCkbRpc::default();
+        rpc.push(
+            CellOutput::new_builder()
+                .lock(Withdraw::lock(&type_ids.metadata_type_id, user))
+                .type_(Some(Xudt::type_(&type_ids.xudt_owner.pack())).pack())
+                .build(),
+            Bytes::default(),
+        );
+        rpc.cells[0].output_data = None;
+
+        let err = WithdrawTxBuilder::new(&rpc, type_ids, user, 3)
+            .build_tx()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CkbTxErr>(),
+            Some(CkbTxErr::CellDataNotFound(_))
+        ));
+    }
+
     // #[tokio::test]
     async fn _withdraw_test1_tx() {
         _withdraw_tx(1).await;
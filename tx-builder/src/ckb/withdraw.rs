@@ -56,23 +56,33 @@ impl<'a, C: CkbRpc> IWithdrawTxBuilder<'a, C> for WithdrawTxBuilder<'a, C> {
             .previous_output(withdraw_cell.out_point.into())
             .build()];
 
+        let withdraw_data = withdraw_cell
+            .output_data
+            .ok_or_else(|| CkbTxErr::CellDataNotFound("withdraw AT".to_owned()))?
+            .into_bytes();
+        let (withdraw_data, unlock_amount) = self.build_withdraw_data(withdraw_data)?;
+
         // AT cell
         let token_amount = self.add_token_to_inputs(&mut inputs).await?;
 
-        let withdraw_data = withdraw_cell.output_data.unwrap().into_bytes();
-        let outputs_data = self.build_data(token_amount, withdraw_data).await?;
+        let outputs_data = vec![
+            // withdraw AT cell data
+            withdraw_data,
+            // AT cell data
+            (token_amount + unlock_amount).pack().as_bytes(),
+        ];
 
         let outputs = vec![
             // withdraw AT cell
             CellOutput::new_builder()
                 .lock(self.withdraw_lock.clone())
                 .type_(Some(self.xudt.clone()).pack())
-                .build_exact_capacity(Capacity::bytes(outputs_data[1].len())?)?,
+                .build_exact_capacity(Capacity::bytes(outputs_data[0].len())?)?,
             // AT cell
             CellOutput::new_builder()
                 .lock(self.token_lock.clone())
                 .type_(Some(self.xudt.clone()).pack())
-                .build_exact_capacity(Capacity::bytes(outputs_data[0].len())?)?,
+                .build_exact_capacity(Capacity::bytes(outputs_data[1].len())?)?,
         ];
 
         let cell_deps = vec![
@@ -135,14 +145,12 @@ impl<'a, C: CkbRpc> WithdrawTxBuilder<'a, C> {
         Ok(amount)
     }
 
-    async fn build_data(
-        &self,
-        mut wallet_amount: Amount,
-        mut withdraw_data: Bytes,
-    ) -> CkbTxResult<Vec<Bytes>> {
-        let mut total_withdraw_amount = new_u128(&withdraw_data[..TOKEN_BYTES]);
-
-        let withdraw_data = WithdrawAtCellData::new_unchecked(withdraw_data.split_off(TOKEN_BYTES));
+    /// Build the data of the output withdraw AT cell, returning it with the
+    /// amount unlocked by the current epoch. Fails if nothing is unlocked.
+    pub(crate) fn build_withdraw_data(&self, withdraw_data: Bytes) -> CkbTxResult<(Bytes, Amount)> {
+        let (total_withdraw_amount, withdraw_data) = safe_split(withdraw_data, TOKEN_BYTES)?;
+        let mut total_withdraw_amount = new_u128(&total_withdraw_amount);
+        let withdraw_data = WithdrawAtCellData::new_unchecked(withdraw_data);
 
         let mut output_withdraw_infos = WithdrawInfos::new_builder();
         let mut unlock_amount = 0;
@@ -156,13 +164,15 @@ impl<'a, C: CkbRpc> WithdrawTxBuilder<'a, C> {
             }
         }
 
-        wallet_amount += unlock_amount;
+        if unlock_amount == 0 {
+            return Err(CkbTxErr::NothingToWithdraw(self.current_epoch));
+        }
+
         total_withdraw_amount -= unlock_amount;
 
         let inner_withdraw_data = withdraw_data.lock();
 
-        Ok(vec![
-            // withdraw AT cell data
+        Ok((
             token_cell_data(
                 total_withdraw_amount,
                 withdraw_data
@@ -176,8 +186,7 @@ impl<'a, C: CkbRpc> WithdrawTxBuilder<'a, C> {
                     .build()
                     .as_bytes(),
             ),
-            // AT cell data
-            wallet_amount.pack().as_bytes(),
-        ])
+            unlock_amount,
+        ))
     }
 }
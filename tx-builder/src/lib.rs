@@ -1,9 +1,14 @@
 pub mod axon;
 pub mod ckb;
 
-use common::types::tx_builder::NetworkType;
+use common::types::tx_builder::{CellDepOverride, NetworkType, ScriptKind};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub fn set_network_type(network_type: NetworkType) {
     (*ckb::NETWORK_TYPE).swap(Arc::new(network_type));
 }
+
+pub fn set_cell_dep_overrides(overrides: HashMap<ScriptKind, CellDepOverride>) {
+    (*ckb::CELL_DEP_OVERRIDES).swap(Arc::new(overrides));
+}